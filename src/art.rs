@@ -331,20 +331,8 @@ __,. -- ~~ ~|    o   `\|      |~ ~~ -- . __
 }
 impl AsciiArt {
     pub fn get_art(&self, condition: &str, is_day: bool) -> &str {
-        let art_key = match condition {
-            c if c.contains("clear") => "sunny",
-            c if c.contains("clouds") => "cloudy",
-            c if c.contains("rain") || c.contains("drizzle") => "rainy",
-            c if c.contains("thunderstorm") => "stormy",
-            c if c.contains("snow") => "snowy",
-            c if c.contains("smoke")
-                || c.contains("haze")
-                || c.contains("fog")
-                || c.contains("mist") =>
-            {
-                "smoke"
-            }
-            _ => return "No art available for this condition",
+        let Some(art_key) = art_key(condition) else {
+            return "No art available for this condition";
         };
 
         if is_day {
@@ -353,4 +341,78 @@ impl AsciiArt {
             self.art.get(art_key).unwrap().night_art.as_str()
         }
     }
+
+    // Small 5 line icon for places where the full art does not fit, like the forecast panel
+    pub fn get_icon(&self, condition: &str) -> &'static str {
+        match art_key(condition) {
+            Some("sunny") => SUNNY_ICON,
+            Some("cloudy") => CLOUDY_ICON,
+            Some("rainy") => RAINY_ICON,
+            Some("stormy") => STORMY_ICON,
+            Some("snowy") => SNOWY_ICON,
+            Some("smoke") => SMOKE_ICON,
+            _ => UNKNOWN_ICON,
+        }
+    }
 }
+
+fn art_key(condition: &str) -> Option<&'static str> {
+    let art_key = match condition {
+        c if c.contains("clear") => "sunny",
+        c if c.contains("clouds") => "cloudy",
+        c if c.contains("rain") || c.contains("drizzle") => "rainy",
+        c if c.contains("thunderstorm") => "stormy",
+        c if c.contains("snow") => "snowy",
+        c if c.contains("smoke")
+            || c.contains("haze")
+            || c.contains("fog")
+            || c.contains("mist") =>
+        {
+            "smoke"
+        }
+        _ => return None,
+    };
+    Some(art_key)
+}
+
+const SUNNY_ICON: &str = r"   \   /
+    .-.
+ - (   ) -
+    `-'
+   /   \";
+
+const CLOUDY_ICON: &str = r"
+     .--.
+  .-(    ).
+ (___.__)__)
+";
+
+const RAINY_ICON: &str = r"     .-.
+    (   ).
+   (___(__)
+    ' ' ' '
+   ' ' ' '";
+
+const STORMY_ICON: &str = r"     .-.
+    (   ).
+   (___(__)
+    /_ /_
+     /  /";
+
+const SNOWY_ICON: &str = r"     .-.
+    (   ).
+   (___(__)
+    *  *  *
+   *  *  *";
+
+const SMOKE_ICON: &str = r"
+ _ - _ - _ -
+  _ - _ - _
+ _ - _ - _ -
+";
+
+const UNKNOWN_ICON: &str = r"    .-.
+     __)
+    (
+     `-'
+      *";
@@ -0,0 +1,470 @@
+use crate::error::TermocastError;
+use crate::location::{Location, Place};
+use crate::provider::WeatherProvider;
+use crate::types::{Forecast, WeatherDetails};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// What a cache file holds, the response as the provider returned it plus when that was
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    fetched_at: u64,
+    data: T,
+}
+
+// Last response per location, one JSON file each under `$XDG_CACHE_HOME/termocast`.
+// The cache is best effort, a file that cannot be read or written is simply a miss.
+#[derive(Debug)]
+pub struct Cache {
+    dir: Option<PathBuf>,
+    ttl: Duration,
+}
+
+impl Cache {
+    pub fn new(dir: Option<PathBuf>, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    // The cached value and how old it is
+    async fn read<T: DeserializeOwned>(&self, key: &str) -> Option<(Duration, T)> {
+        let path = self.dir.as_ref()?.join(format!("{}.json", key));
+        let text = tokio::fs::read_to_string(path).await.ok()?;
+        let entry: Entry<T> = serde_json::from_str(&text).ok()?;
+        Some((
+            Duration::from_secs(now().saturating_sub(entry.fetched_at)),
+            entry.data,
+        ))
+    }
+
+    async fn write<T: Serialize>(&self, key: &str, data: &T) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let entry = Entry {
+            fetched_at: now(),
+            data,
+        };
+        let Ok(text) = serde_json::to_string(&entry) else {
+            return;
+        };
+        if tokio::fs::create_dir_all(dir).await.is_ok() {
+            let _ = tokio::fs::write(dir.join(format!("{}.json", key)), text).await;
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+// File name for a request, e.g. `current-name-london` or `forecast-coord-51.507_-0.128`
+fn key(kind: &str, location: &Location) -> String {
+    let location = match location {
+        Location::Name(name) => format!("name-{}", name),
+        Location::Coordinates { lat, lon, .. } => format!("coord-{:.3}_{:.3}", lat, lon),
+        Location::Zip { code, country } => {
+            format!("zip-{}_{}", code, country.as_deref().unwrap_or_default())
+        }
+        Location::Id(id) => format!("id-{}", id),
+    };
+    sanitize(&format!("{}-{}", kind, location))
+}
+
+// Keep keys usable as file names whatever the user typed
+fn sanitize(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '.' => {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+            _ => '_',
+        })
+        .collect()
+}
+
+// Wraps a provider with the cache. Fresh entries are served without asking the service,
+// except to refreshes, and when the service cannot be reached the last response is
+// served regardless of its age with `WeatherDetails::stale` set. In offline mode the
+// service is never asked.
+#[derive(Debug)]
+pub struct CachedProvider {
+    pub inner: Arc<dyn WeatherProvider>,
+    pub cache: Cache,
+    pub offline: bool,
+}
+
+impl CachedProvider {
+    // The value and whether it is an old copy that could not be refreshed. `refresh`
+    // skips a fresh copy, it is still the fallback when the service cannot be reached.
+    async fn get<T, F>(
+        &self,
+        key: &str,
+        what: String,
+        refresh: bool,
+        fetch: F,
+    ) -> Result<(T, bool), TermocastError>
+    where
+        T: Serialize + DeserializeOwned + Send,
+        F: Future<Output = Result<T, TermocastError>> + Send,
+    {
+        let cached = self.cache.read::<T>(key).await;
+        let cached = match cached {
+            Some((age, data)) if !refresh && age < self.cache.ttl => return Ok((data, false)),
+            cached => cached.map(|(_, data)| data),
+        };
+        if self.offline {
+            return cached
+                .map(|data| (data, true))
+                .ok_or(TermocastError::NotCached(what));
+        }
+
+        match fetch.await {
+            Ok(data) => {
+                self.cache.write(key, &data).await;
+                Ok((data, false))
+            }
+            Err(e) if e.is_unreachable() => cached.map(|data| (data, true)).ok_or(e),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl CachedProvider {
+    async fn fetch_current(
+        &self,
+        location: &Location,
+        refresh: bool,
+    ) -> Result<WeatherDetails, TermocastError> {
+        let (mut details, stale): (WeatherDetails, bool) = self
+            .get(
+                &key("current", location),
+                location.to_string(),
+                refresh,
+                self.inner.current(location),
+            )
+            .await?;
+        details.stale = stale;
+        Ok(details)
+    }
+
+    async fn fetch_forecast(
+        &self,
+        location: &Location,
+        refresh: bool,
+    ) -> Result<Forecast, TermocastError> {
+        let (forecast, _) = self
+            .get(
+                &key("forecast", location),
+                location.to_string(),
+                refresh,
+                self.inner.forecast(location),
+            )
+            .await?;
+        Ok(forecast)
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for CachedProvider {
+    async fn current(&self, location: &Location) -> Result<WeatherDetails, TermocastError> {
+        self.fetch_current(location, false).await
+    }
+
+    async fn forecast(&self, location: &Location) -> Result<Forecast, TermocastError> {
+        self.fetch_forecast(location, false).await
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Vec<Place>, TermocastError> {
+        let (places, _) = self
+            .get(
+                &key("geocode", &Location::Name(query.to_string())),
+                query.to_string(),
+                false,
+                self.inner.geocode(query),
+            )
+            .await?;
+        Ok(places)
+    }
+
+    async fn refresh_current(&self, location: &Location) -> Result<WeatherDetails, TermocastError> {
+        self.fetch_current(location, true).await
+    }
+
+    async fn refresh_forecast(&self, location: &Location) -> Result<Forecast, TermocastError> {
+        self.fetch_forecast(location, true).await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const WEATHER: &str = include_str!("../tests/fixtures/openweathermap/weather.json");
+    const FORECAST: &str = include_str!("../tests/fixtures/openweathermap/forecast.json");
+
+    // A directory of its own under the system temp dir, removed again when dropped
+    pub struct TempDir(pub PathBuf);
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            static COUNT: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "termocast-{}-{}-{}",
+                name,
+                std::process::id(),
+                COUNT.fetch_add(1, Ordering::Relaxed)
+            ));
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Answer {
+        Reading,
+        // 503, the service is down
+        Unavailable,
+        // 401, asking again will not help
+        Unauthorized,
+    }
+
+    // Answers with the London fixtures or the error it is told to, counting the calls
+    #[derive(Debug)]
+    pub struct Stub {
+        pub answer: Mutex<Answer>,
+        pub calls: AtomicUsize,
+    }
+
+    impl Default for Stub {
+        fn default() -> Self {
+            Self {
+                answer: Mutex::new(Answer::Reading),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl Stub {
+        pub fn answer(&self, answer: Answer) {
+            *self.answer.lock().unwrap() = answer;
+        }
+
+        pub fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+
+        fn respond<T: DeserializeOwned>(&self, json: &str) -> Result<T, TermocastError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let status = match *self.answer.lock().unwrap() {
+                Answer::Reading => return Ok(serde_json::from_str(json)?),
+                Answer::Unavailable => reqwest::StatusCode::SERVICE_UNAVAILABLE,
+                Answer::Unauthorized => reqwest::StatusCode::UNAUTHORIZED,
+            };
+            Err(TermocastError::Http {
+                status,
+                message: "stub".to_string(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl WeatherProvider for Stub {
+        async fn current(&self, _: &Location) -> Result<WeatherDetails, TermocastError> {
+            self.respond(WEATHER)
+        }
+
+        async fn forecast(&self, _: &Location) -> Result<Forecast, TermocastError> {
+            self.respond(FORECAST)
+        }
+
+        async fn geocode(&self, query: &str) -> Result<Vec<Place>, TermocastError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(TermocastError::CityNotFound(query.to_string()))
+        }
+    }
+
+    fn cached(stub: &Arc<Stub>, dir: &TempDir, offline: bool) -> CachedProvider {
+        CachedProvider {
+            inner: stub.clone(),
+            cache: Cache::new(Some(dir.0.clone()), Duration::from_secs(300)),
+            offline,
+        }
+    }
+
+    // Every entry is already too old to be used as it is
+    fn expired(stub: &Arc<Stub>, dir: &TempDir) -> CachedProvider {
+        CachedProvider {
+            cache: Cache::new(Some(dir.0.clone()), Duration::ZERO),
+            ..cached(stub, dir, false)
+        }
+    }
+
+    fn london() -> Location {
+        Location::Name("London".to_string())
+    }
+
+    #[tokio::test]
+    async fn refreshes_skip_a_fresh_copy() {
+        let dir = TempDir::new("refresh");
+        let stub = Arc::new(Stub::default());
+        let provider = cached(&stub, &dir, false);
+
+        provider.current(&london()).await.unwrap();
+        provider.current(&london()).await.unwrap();
+        assert_eq!(stub.calls(), 1);
+
+        let details = provider.refresh_current(&london()).await.unwrap();
+        assert!(!details.stale);
+        provider.refresh_current(&london()).await.unwrap();
+        assert_eq!(stub.calls(), 3);
+
+        provider.forecast(&london()).await.unwrap();
+        provider.refresh_forecast(&london()).await.unwrap();
+        assert_eq!(stub.calls(), 5);
+    }
+
+    #[tokio::test]
+    async fn refreshes_fall_back_to_the_copy_when_unreachable() {
+        let dir = TempDir::new("refresh-down");
+        let stub = Arc::new(Stub::default());
+        let provider = cached(&stub, &dir, false);
+        provider.current(&london()).await.unwrap();
+
+        stub.answer(Answer::Unavailable);
+        let details = provider.refresh_current(&london()).await.unwrap();
+        assert!(details.stale);
+        assert_eq!(details.name, "London");
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_fresh_copy_makes_no_call() {
+        let dir = TempDir::new("fresh");
+        let stub = Arc::new(Stub::default());
+        let provider = cached(&stub, &dir, false);
+
+        let first = provider.current(&london()).await.unwrap();
+        stub.answer(Answer::Unauthorized);
+        let second = provider.current(&london()).await.unwrap();
+        assert_eq!(stub.calls(), 1);
+        assert!(!second.stale);
+        assert_eq!(second.main.temp, first.main.temp);
+        assert!(dir.0.join("current-name-london.json").exists());
+    }
+
+    #[tokio::test]
+    async fn an_old_copy_is_fetched_again() {
+        let dir = TempDir::new("expired");
+        let stub = Arc::new(Stub::default());
+        let provider = expired(&stub, &dir);
+
+        provider.current(&london()).await.unwrap();
+        let details = provider.current(&london()).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+        assert!(!details.stale);
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_service_gives_the_stale_copy() {
+        let dir = TempDir::new("stale");
+        let stub = Arc::new(Stub::default());
+        let provider = expired(&stub, &dir);
+        provider.current(&london()).await.unwrap();
+        provider.forecast(&london()).await.unwrap();
+
+        stub.answer(Answer::Unavailable);
+        let details = provider.current(&london()).await.unwrap();
+        assert!(details.stale);
+        assert_eq!(details.name, "London");
+        assert!(provider.forecast(&london()).await.is_ok());
+        assert_eq!(stub.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_service_without_a_copy_is_an_error() {
+        let dir = TempDir::new("down");
+        let stub = Arc::new(Stub::default());
+        stub.answer(Answer::Unavailable);
+        let result = cached(&stub, &dir, false).current(&london()).await;
+        assert!(matches!(result, Err(TermocastError::Http { status, .. }) if status == 503));
+    }
+
+    #[tokio::test]
+    async fn client_errors_do_not_fall_back() {
+        let dir = TempDir::new("unauthorized");
+        let stub = Arc::new(Stub::default());
+        let provider = expired(&stub, &dir);
+        provider.current(&london()).await.unwrap();
+
+        // a wrong API key has to be seen, not hidden behind an old reading
+        stub.answer(Answer::Unauthorized);
+        let result = provider.current(&london()).await;
+        assert!(matches!(result, Err(TermocastError::Http { status, .. }) if status == 401));
+    }
+
+    #[tokio::test]
+    async fn offline_without_a_copy_is_not_cached() {
+        let dir = TempDir::new("offline-empty");
+        let stub = Arc::new(Stub::default());
+        let result = cached(&stub, &dir, true).current(&london()).await;
+        assert!(matches!(result, Err(TermocastError::NotCached(place)) if place == "London"));
+        assert_eq!(stub.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn offline_serves_any_copy_without_calls() {
+        let dir = TempDir::new("offline");
+        let stub = Arc::new(Stub::default());
+        expired(&stub, &dir).current(&london()).await.unwrap();
+
+        let offline = CachedProvider {
+            offline: true,
+            ..expired(&stub, &dir)
+        };
+        let details = offline.current(&london()).await.unwrap();
+        assert!(details.stale);
+        assert!(offline.refresh_current(&london()).await.is_ok());
+        assert_eq!(stub.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn without_a_directory_nothing_is_kept() {
+        let stub = Arc::new(Stub::default());
+        let provider = CachedProvider {
+            inner: stub.clone(),
+            cache: Cache::new(None, Duration::from_secs(300)),
+            offline: false,
+        };
+        provider.current(&london()).await.unwrap();
+        provider.current(&london()).await.unwrap();
+        assert_eq!(stub.calls(), 2);
+    }
+
+    #[test]
+    fn keys_are_file_names() {
+        assert_eq!(key("current", &london()), "current-name-london");
+        assert_eq!(
+            key("forecast", &Location::Name("São Paulo/../x".to_string())),
+            "forecast-name-são_paulo_.._x"
+        );
+        let place = Location::Coordinates {
+            lat: 51.50853,
+            lon: -0.12574,
+            name: Some("London".to_string()),
+        };
+        assert_eq!(key("current", &place), "current-coord-51.509_-0.126");
+    }
+}
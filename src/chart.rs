@@ -0,0 +1,183 @@
+use crate::theme::Theme;
+use crate::types::Forecast;
+use crate::units::Units;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    symbols::{self, border},
+    text::Line,
+    widgets::{Axis, Block, Chart, Dataset, GraphType, Sparkline, Widget},
+};
+
+// 16 slots of 3 hours, the next two days
+const HOURLY_SLOTS: usize = 16;
+
+// Temperature line chart with precipitation probability and wind speed sparklines
+// for the upcoming forecast slots
+pub struct HourlyChart<'a> {
+    pub forecast: &'a Forecast,
+    pub units: &'a Units,
+    pub theme: &'a Theme,
+}
+
+impl Widget for HourlyChart<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let entries = &self.forecast.list[..self.forecast.list.len().min(HOURLY_SLOTS)];
+        let Some(first) = entries.first() else {
+            return;
+        };
+
+        let hours = |dt: u64| (dt.saturating_sub(first.dt)) as f64 / 3600.0;
+        let temperatures: Vec<(f64, f64)> = entries
+            .iter()
+            .map(|e| {
+                (
+                    hours(e.dt),
+                    self.units.temperature.convert(e.main.temp) as f64,
+                )
+            })
+            .collect();
+        let span = temperatures.last().map_or(0.0, |&(h, _)| h);
+        let (low, high) = temperatures.iter().fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(low, high), &(_, t)| (low.min(t), high.max(t)),
+        );
+        let (low, high) = (low.floor() - 1.0, high.ceil() + 1.0);
+
+        let precipitation: Vec<u64> = entries
+            .iter()
+            .map(|e| (e.pop * 100.0).round() as u64)
+            .collect();
+        let wind: Vec<u64> = entries
+            .iter()
+            .map(|e| self.units.wind.convert(e.wind.speed).round() as u64)
+            .collect();
+        // sparklines draw one bar per value, repeat each slot to use the whole width
+        let repeat = (area.width.saturating_sub(2) as usize / entries.len()).max(1);
+
+        let [temperature_area, precipitation_area, wind_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .areas(area);
+
+        let dataset = Dataset::default()
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(self.theme.temperature))
+            .data(&temperatures);
+        Chart::new(vec![dataset])
+            .block(panel_block(
+                self.theme,
+                format!(" Temperature next {:.0}h ", span),
+            ))
+            .x_axis(Axis::default().bounds([0.0, span]).labels([
+                "now".to_string(),
+                format!("+{:.0}h", span / 2.0),
+                format!("+{:.0}h", span),
+            ]))
+            .y_axis(Axis::default().bounds([low, high]).labels([
+                format!("{:.0}{}", low, self.units.temperature.symbol()),
+                format!("{:.0}{}", high, self.units.temperature.symbol()),
+            ]))
+            .render(temperature_area, buf);
+
+        Sparkline::default()
+            .block(panel_block(
+                self.theme,
+                format!(
+                    " Precipitation up to {}% ",
+                    precipitation.iter().max().copied().unwrap_or(0)
+                ),
+            ))
+            .data(stretch(&precipitation, repeat))
+            .max(100)
+            .style(Style::default().fg(self.theme.precipitation))
+            .render(precipitation_area, buf);
+
+        Sparkline::default()
+            .block(panel_block(
+                self.theme,
+                format!(
+                    " Wind up to {} {} ",
+                    wind.iter().max().copied().unwrap_or(0),
+                    self.units.wind.symbol()
+                ),
+            ))
+            .data(stretch(&wind, repeat))
+            .style(Style::default().fg(self.theme.wind))
+            .render(wind_area, buf);
+    }
+}
+
+fn panel_block(theme: &Theme, title: String) -> Block<'static> {
+    Block::bordered()
+        .title(Line::from(title).bold())
+        .border_set(border::ROUNDED)
+        .border_style(Style::default().fg(theme.accent))
+}
+
+fn stretch(values: &[u64], repeat: usize) -> Vec<u64> {
+    values
+        .iter()
+        .flat_map(|&v| std::iter::repeat_n(v, repeat))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORECAST: &str = include_str!("../tests/fixtures/openweathermap/forecast.json");
+
+    // The rendered buffer as text, one line per row
+    fn render(forecast: &Forecast, width: u16, height: u16) -> String {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        HourlyChart {
+            forecast,
+            units: &Units::default(),
+            theme: &Theme::default(),
+        }
+        .render(area, &mut buf);
+        (0..height)
+            .map(|y| (0..width).map(|x| buf[(x, y)].symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn renders_titles_and_axis_labels() {
+        let forecast: Forecast = serde_json::from_str(FORECAST).unwrap();
+        let text = render(&forecast, 60, 20);
+
+        assert!(text.contains(" Temperature next 12h "), "{}", text);
+        assert!(text.contains(" Precipitation up to 40% "), "{}", text);
+        assert!(text.contains(" Wind up to 5 m/s "), "{}", text);
+        for label in ["now", "+6h", "+12h", "8°C", "15°C"] {
+            assert!(text.contains(label), "no {} in\n{}", label, text);
+        }
+    }
+
+    #[test]
+    fn renders_a_single_slot() {
+        let mut forecast: Forecast = serde_json::from_str(FORECAST).unwrap();
+        forecast.list.truncate(1);
+        let text = render(&forecast, 60, 20);
+
+        assert!(text.contains(" Temperature next 0h "), "{}", text);
+        assert!(text.contains(" Precipitation up to 0% "), "{}", text);
+        assert!(text.contains("now"), "{}", text);
+    }
+
+    #[test]
+    fn renders_nothing_without_slots() {
+        let mut forecast: Forecast = serde_json::from_str(FORECAST).unwrap();
+        forecast.list.clear();
+        let text = render(&forecast, 60, 20);
+
+        assert!(text.trim().is_empty());
+    }
+}
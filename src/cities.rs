@@ -0,0 +1,290 @@
+use crate::location::Place;
+use std::cmp::Reverse;
+use std::sync::LazyLock;
+
+// Bundled list of large cities from GeoNames, largest first. Columns are name, country
+// code, admin region, latitude, longitude and population, separated by tabs.
+const CITIES: &str = include_str!("../data/cities.tsv");
+
+static INDEX: LazyLock<Index> = LazyLock::new(|| Index::new(CITIES));
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct City {
+    pub name: String,
+    pub country: String,
+    pub admin: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub population: u64,
+}
+
+impl City {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let city = Self {
+            name: fields.next()?.to_string(),
+            country: fields.next()?.to_string(),
+            admin: fields.next()?.to_string(),
+            lat: fields.next()?.parse().ok()?,
+            lon: fields.next()?.parse().ok()?,
+            population: fields.next()?.parse().ok()?,
+        };
+        Some(city)
+    }
+
+    pub fn place(&self) -> Place {
+        Place {
+            name: self.name.clone(),
+            state: Some(self.admin.clone()).filter(|admin| *admin != self.name),
+            country: Some(self.country.clone()),
+            lat: self.lat,
+            lon: self.lon,
+        }
+    }
+
+    // "São Paulo,BR", the form completions are offered in
+    pub fn query(&self) -> String {
+        format!("{},{}", self.name, self.country)
+    }
+}
+
+// The cities with their folded names sorted, so a prefix is found with a binary search
+// and the cities sharing it sit next to each other
+struct Index {
+    cities: Vec<City>,
+    keys: Vec<(String, usize)>,
+}
+
+impl Index {
+    fn new(data: &str) -> Self {
+        let cities: Vec<City> = data
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(City::parse)
+            .collect();
+        let mut keys: Vec<(String, usize)> = cities
+            .iter()
+            .enumerate()
+            .map(|(i, city)| (fold(&city.name), i))
+            .collect();
+        keys.sort();
+        Self { cities, keys }
+    }
+
+    // Entries whose key starts with `prefix`
+    fn prefixed(&self, prefix: &str) -> &[(String, usize)] {
+        let start = self.keys.partition_point(|(key, _)| key.as_str() < prefix);
+        let len = self.keys[start..]
+            .iter()
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .count();
+        &self.keys[start..start + len]
+    }
+
+    fn cities<'a>(&'a self, keys: impl Iterator<Item = &'a (String, usize)>) -> Vec<&'a City> {
+        let mut cities: Vec<&City> = keys.map(|(_, i)| &self.cities[*i]).collect();
+        cities.sort_by_key(|city| Reverse(city.population));
+        cities
+    }
+}
+
+// Cities called exactly `name`, biggest first. A trailing `,CC` narrows it to a country.
+pub fn lookup(query: &str) -> Vec<&'static City> {
+    let (name, country) = split_query(query);
+    let name = fold(name);
+    let matches = INDEX.prefixed(&name).iter().filter(|(key, _)| *key == name);
+    INDEX
+        .cities(matches)
+        .into_iter()
+        .filter(|city| matches_country(city, country))
+        .collect()
+}
+
+// Cities whose name starts with what was typed, biggest first. When nothing does, names
+// within a typo or two are tried instead, among those with the same first letter.
+pub fn search(query: &str, limit: usize) -> Vec<&'static City> {
+    let (name, country) = split_query(query);
+    let name = fold(name);
+    if name.is_empty() {
+        return Vec::new();
+    }
+
+    let mut found: Vec<&City> = INDEX
+        .cities(INDEX.prefixed(&name).iter())
+        .into_iter()
+        .filter(|city| matches_country(city, country))
+        .collect();
+    if found.is_empty() && name.chars().count() >= 3 {
+        let first: String = name.chars().take(1).collect();
+        let allowed = if name.chars().count() > 5 { 2 } else { 1 };
+        let close = INDEX
+            .prefixed(&first)
+            .iter()
+            .filter(|(key, _)| typo_distance(&name, key) <= allowed);
+        found = INDEX
+            .cities(close)
+            .into_iter()
+            .filter(|city| matches_country(city, country))
+            .collect();
+    }
+    found.truncate(limit);
+    found
+}
+
+// Whether the query ends in a country code, "Paris,FR"
+pub fn names_country(query: &str) -> bool {
+    split_query(query)
+        .1
+        .is_some_and(|country| !country.is_empty())
+}
+
+// "Paris,F" is the name and the start of a country code
+fn split_query(query: &str) -> (&str, Option<&str>) {
+    match query.rsplit_once(',') {
+        Some((name, country)) => (name.trim(), Some(country.trim())),
+        None => (query.trim(), None),
+    }
+}
+
+fn matches_country(city: &City, country: Option<&str>) -> bool {
+    country.is_none_or(|country| {
+        city.country
+            .to_ascii_lowercase()
+            .starts_with(&country.to_ascii_lowercase())
+    })
+}
+
+// Lowercase without accents, so "sao paulo" finds "São Paulo"
+fn fold(s: &str) -> String {
+    s.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            c => c,
+        })
+        .collect()
+}
+
+// Edits (insertions, deletions, substitutions and swapped neighbours) needed to turn
+// `typed` into the start of `name`, so an unfinished name is not counted against it
+fn typo_distance(typed: &str, name: &str) -> usize {
+    let a: Vec<char> = typed.chars().collect();
+    let b: Vec<char> = name.chars().collect();
+    // rows[i][j] is the distance between a[..i] and b[..j]
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    // any prefix of the name will do
+    rows[a.len()].iter().copied().min().unwrap_or(a.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(cities: &[&City]) -> Vec<String> {
+        cities.iter().map(|city| city.query()).collect()
+    }
+
+    #[test]
+    fn lookup_wants_the_whole_name() {
+        assert_eq!(names(&lookup("london")), ["London,GB"]);
+        assert!(lookup("Lond").is_empty());
+        assert!(lookup("Atlantis").is_empty());
+    }
+
+    #[test]
+    fn lookup_lists_namesakes_biggest_first() {
+        let found = lookup("Springfield");
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].admin, "Missouri");
+        assert!(found.windows(2).all(|w| w[0].population >= w[1].population));
+    }
+
+    #[test]
+    fn country_code_narrows_the_matches() {
+        assert_eq!(lookup("Springfield,US").len(), 3);
+        assert_eq!(names(&lookup("Paris, fr")), ["Paris,FR"]);
+        assert_eq!(names(&lookup("Paris,F")), ["Paris,FR"]);
+        assert!(lookup("Paris,US").is_empty());
+        assert!(search("Lon,CA", 6).iter().all(|city| city.country == "CA"));
+    }
+
+    #[test]
+    fn names_country_needs_a_code() {
+        assert!(names_country("Paris,FR"));
+        assert!(names_country("Paris, f"));
+        assert!(!names_country("Paris"));
+        assert!(!names_country("Paris,"));
+    }
+
+    #[test]
+    fn search_finds_prefixes() {
+        let found = names(&search("mon", 6));
+        assert!(found.contains(&"Montreal,CA".to_string()));
+        assert!(found.contains(&"Montevideo,UY".to_string()));
+        assert!(found.iter().all(|name| name.starts_with("Mon")));
+        assert_eq!(search("spring", 2).len(), 2);
+        assert!(search("  ", 6).is_empty());
+    }
+
+    #[test]
+    fn accents_are_ignored() {
+        assert_eq!(fold("São Paulo"), "sao paulo");
+        assert_eq!(fold("KRAKÓW"), "krakow");
+        assert_eq!(names(&lookup("sao paulo")), ["São Paulo,BR"]);
+        assert_eq!(names(&search("bogota", 6)), ["Bogotá,CO"]);
+        // and typed accents find names stored without them
+        assert_eq!(names(&lookup("Zürich")), ["Zurich,CH"]);
+    }
+
+    #[test]
+    fn small_typos_are_tolerated() {
+        assert_eq!(names(&search("Lodnon", 6)), ["London,GB"]);
+        assert_eq!(names(&search("Berlim", 6)), ["Berlin,DE"]);
+        // longer names may be two edits off, which lets Manchester in after it
+        assert_eq!(names(&search("Muinch", 6))[0], "Munich,DE");
+        // too short to guess, and the first letter has to be right
+        assert!(search("Lx", 6).is_empty());
+        assert!(search("Kondon", 6).is_empty());
+    }
+
+    #[test]
+    fn typo_distance_counts_edits_against_a_prefix() {
+        assert_eq!(typo_distance("lon", "london"), 0);
+        assert_eq!(typo_distance("lodnon", "london"), 1);
+        assert_eq!(typo_distance("berlim", "berlin"), 1);
+        assert_eq!(typo_distance("londn", "london"), 1);
+        assert_eq!(typo_distance("lonxdon", "london"), 1);
+        assert_eq!(typo_distance("xyz", "london"), 3);
+        assert_eq!(typo_distance("", "london"), 0);
+    }
+
+    #[test]
+    fn every_line_of_the_data_parses() {
+        let lines = CITIES.lines().filter(|line| !line.starts_with('#')).count();
+        assert_eq!(INDEX.cities.len(), lines);
+        assert_eq!(INDEX.keys.len(), lines);
+    }
+}
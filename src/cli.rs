@@ -0,0 +1,218 @@
+use crate::art::AsciiArt;
+use crate::cities;
+use crate::error::TermocastError;
+use crate::location::Location;
+use crate::output::{self, OutputFormat};
+use crate::provider::{ProviderKind, WeatherProvider};
+use crate::units::{PressureUnit, TemperatureUnit, UnitSystem, Units, WindUnit};
+use clap::Parser;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Location to look up: a city name, `City,CC`, `lat,lon`, `zip:94040,us` or
+    /// `id:2643743`. When given, the reading is printed once and termocast exits instead
+    /// of starting the interactive interface.
+    pub city: Vec<String>,
+
+    /// Print the ascii art for the current condition above the reading (plain format only)
+    #[arg(long)]
+    pub art: bool,
+
+    /// Output format of the one-shot mode
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub format: OutputFormat,
+
+    /// Unit system for temperature, wind and pressure [default: metric]
+    #[arg(long, value_enum)]
+    pub units: Option<UnitSystem>,
+
+    /// Temperature unit, overrides the one from --units
+    #[arg(long, value_enum)]
+    pub temperature_unit: Option<TemperatureUnit>,
+
+    /// Wind speed unit, overrides the one from --units
+    #[arg(long, value_enum)]
+    pub wind_unit: Option<WindUnit>,
+
+    /// Pressure unit, overrides the one from --units
+    #[arg(long, value_enum)]
+    pub pressure_unit: Option<PressureUnit>,
+
+    /// Weather backend to use: openweathermap or open-meteo (overrides TERMOCAST_PROVIDER)
+    #[arg(long)]
+    pub provider: Option<ProviderKind>,
+
+    /// Language of the weather descriptions, e.g. de or pt_br (OpenWeatherMap only)
+    #[arg(long = "lang")]
+    pub language: Option<String>,
+
+    /// Scheme and host to send requests to instead of the provider's public API, e.g. a
+    /// caching proxy (overrides TERMOCAST_BASE_URL)
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Never touch the network, only show what is in the cache
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Config file to use instead of $XDG_CONFIG_HOME/termocast/config.toml
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn parse_args() -> Self {
+        Self::parse_from(escape_coordinates(std::env::args_os()))
+    }
+
+    // Multi word names can be passed without quotes, `termocast New York`
+    pub fn location(&self) -> Result<Option<Location>, TermocastError> {
+        let city = self.city.join(" ");
+        if city.trim().is_empty() {
+            return Ok(None);
+        }
+        city.parse()
+            .map(Some)
+            .map_err(TermocastError::InvalidLocation)
+    }
+}
+
+// Southern and western coordinates start with a minus, `-33.8688,151.2093`, which clap
+// would take for a flag. None of the options takes a number, so arguments starting with
+// a minus and a digit are moved behind `--`, where everything is part of the location.
+fn escape_coordinates<I, T>(args: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    let end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    let is_coordinate = |arg: &OsString| {
+        arg.to_str()
+            .and_then(|arg| arg.strip_prefix('-'))
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+    };
+    let (coordinates, mut escaped): (Vec<OsString>, Vec<OsString>) =
+        args[..end].iter().cloned().partition(is_coordinate);
+    if coordinates.is_empty() {
+        return args;
+    }
+    escaped.push("--".into());
+    escaped.extend(coordinates);
+    escaped.extend(args.into_iter().skip(end + 1));
+    escaped
+}
+
+// Fetch once, print the reading to stdout and report failures through the exit status
+pub async fn run_once(
+    provider: &dyn WeatherProvider,
+    location: &Location,
+    format: OutputFormat,
+    art: bool,
+    units: &Units,
+    offline: bool,
+) -> ExitCode {
+    // there is no picker here. A name given with its country, or any name offline, is
+    // fetched by the coordinates of the biggest bundled city of that name, everything else
+    // is left to the provider's geocoder as typed.
+    let location = match location {
+        Location::Name(query) if offline || cities::names_country(query) => cities::lookup(query)
+            .first()
+            .map_or_else(|| location.clone(), |city| city.place().location()),
+        location => location.clone(),
+    };
+    let details = match provider.current(&location).await {
+        Ok(details) => details,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::from(e.exit_code());
+        }
+    };
+    if details.stale {
+        eprintln!("Warning: showing cached weather, the service could not be reached.");
+    }
+
+    let art = art.then(AsciiArt::default);
+    match output::render(&details, format, art.as_ref(), units) {
+        Ok(text) => {
+            print!("{}", text);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the way main parses them
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        let args = std::iter::once("termocast").chain(args.iter().copied());
+        Cli::try_parse_from(escape_coordinates(args))
+    }
+
+    fn location(args: &[&str]) -> Option<Location> {
+        parse(args).unwrap().location().unwrap()
+    }
+
+    fn coordinates(lat: f64, lon: f64) -> Option<Location> {
+        Some(Location::Coordinates {
+            lat,
+            lon,
+            name: None,
+        })
+    }
+
+    #[test]
+    fn negative_coordinates_are_a_location() {
+        assert_eq!(
+            location(&["-33.8688,151.2093"]),
+            coordinates(-33.8688, 151.2093)
+        );
+        assert_eq!(location(&["-22.9,-43.2"]), coordinates(-22.9, -43.2));
+        assert_eq!(location(&["40.7,-74.0"]), coordinates(40.7, -74.0));
+    }
+
+    #[test]
+    fn flags_still_work_around_the_location() {
+        let cli = parse(&["--art", "-33.8688,151.2093", "--offline"]).unwrap();
+        assert!(cli.art);
+        assert!(cli.offline);
+        assert_eq!(cli.location().unwrap(), coordinates(-33.8688, 151.2093));
+
+        let cli = parse(&["New", "York", "--format", "json"]).unwrap();
+        assert_eq!(cli.format, OutputFormat::Json);
+        assert_eq!(
+            cli.location().unwrap(),
+            Some(Location::Name("New York".to_string()))
+        );
+    }
+
+    #[test]
+    fn plain_clap_takes_negative_coordinates_for_a_flag() {
+        // what escape_coordinates is there for
+        assert!(Cli::try_parse_from(["termocast", "-33.8688,151.2093"]).is_err());
+        assert_eq!(
+            escape_coordinates(["termocast", "-33.8,151.2", "--art", "--", "x"]),
+            ["termocast", "--art", "--", "-33.8,151.2", "x"]
+        );
+    }
+
+    #[test]
+    fn no_city_starts_the_interface() {
+        assert_eq!(location(&[]), None);
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["-x"]).is_err());
+    }
+}
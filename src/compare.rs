@@ -0,0 +1,190 @@
+use crate::error::TermocastError;
+use crate::theme::Theme;
+use crate::types::WeatherDetails;
+use crate::units::Units;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    symbols::border,
+    text::Line,
+    widgets::{Block, Row, Table, Widget},
+};
+use std::cmp::Ordering;
+
+// One city of the comparison, `result` stays `None` while its fetch is running
+#[derive(Debug)]
+pub struct CompareRow {
+    pub label: String,
+    pub result: Option<Result<WeatherDetails, TermocastError>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    City,
+    Temperature,
+    Condition,
+    Wind,
+    Humidity,
+}
+
+impl SortColumn {
+    pub const ALL: [SortColumn; 5] = [
+        Self::City,
+        Self::Temperature,
+        Self::Condition,
+        Self::Wind,
+        Self::Humidity,
+    ];
+
+    // The column after this one, the last is followed by the first
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|&column| column == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::City => "City",
+            Self::Temperature => "Temperature",
+            Self::Condition => "Condition",
+            Self::Wind => "Wind",
+            Self::Humidity => "Humidity",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub column: SortColumn,
+    pub descending: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Self {
+            column: SortColumn::City,
+            descending: false,
+        }
+    }
+}
+
+impl Sort {
+    // Picking the sorted column again flips the direction
+    pub fn select(self, column: SortColumn) -> Self {
+        Self {
+            column,
+            descending: column == self.column && !self.descending,
+        }
+    }
+}
+
+fn description(details: &WeatherDetails) -> &str {
+    details
+        .weather
+        .first()
+        .map_or("N/A", |w| w.description.as_str())
+}
+
+fn compare_details(a: &WeatherDetails, b: &WeatherDetails, column: SortColumn) -> Ordering {
+    match column {
+        SortColumn::City => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        SortColumn::Temperature => a.main.temp.total_cmp(&b.main.temp),
+        SortColumn::Condition => description(a).cmp(description(b)),
+        SortColumn::Wind => a.wind.speed.total_cmp(&b.wind.speed),
+        SortColumn::Humidity => a.main.humidity.cmp(&b.main.humidity),
+    }
+}
+
+// Rows in display order. Cities still loading or that failed always go last,
+// whatever the direction.
+pub fn sorted_rows(rows: &[CompareRow], sort: Sort) -> Vec<&CompareRow> {
+    let mut sorted: Vec<&CompareRow> = rows.iter().collect();
+    sorted.sort_by(|a, b| match (&a.result, &b.result) {
+        (Some(Ok(a)), Some(Ok(b))) => {
+            let ordering = compare_details(a, b, sort.column);
+            if sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+        (Some(Ok(_)), _) => Ordering::Less,
+        (_, Some(Ok(_))) => Ordering::Greater,
+        _ => Ordering::Equal,
+    });
+    sorted
+}
+
+pub struct CompareTable<'a> {
+    pub rows: &'a [CompareRow],
+    pub sort: Sort,
+    // how to sort, from the keymap
+    pub hint: Option<String>,
+    pub units: &'a Units,
+    pub theme: &'a Theme,
+}
+
+impl Widget for CompareTable<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut block = Block::bordered()
+            .title(Line::from(" Compare ").bold().centered())
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(self.theme.accent));
+        if let Some(hint) = self.hint {
+            block = block.title_bottom(Line::from(hint).italic().centered());
+        }
+
+        if self.rows.is_empty() {
+            Table::default()
+                .header(Row::new(["Save some favorites to compare them here."]))
+                .widths([Constraint::Fill(1)])
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let header = Row::new(SortColumn::ALL.iter().enumerate().map(|(i, &column)| {
+            let arrow = match (column == self.sort.column, self.sort.descending) {
+                (true, false) => " ▲",
+                (true, true) => " ▼",
+                (false, _) => "",
+            };
+            format!("{} {}{}", i + 1, column.title(), arrow)
+        }))
+        .bold()
+        .bottom_margin(1);
+
+        let rows = sorted_rows(self.rows, self.sort)
+            .into_iter()
+            .map(|row| match &row.result {
+                Some(Ok(details)) => Row::new([
+                    details.name.clone(),
+                    self.units.temperature(details.main.temp),
+                    description(details).to_string(),
+                    self.units.wind(details.wind.speed),
+                    format!("{}%", details.main.humidity),
+                ]),
+                Some(Err(e)) => Row::new([row.label.clone(), e.to_string()]),
+                None => Row::new([row.label.clone(), "Fetching...".to_string()]),
+            });
+
+        Table::new(
+            rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .column_spacing(2)
+        .block(block)
+        .render(area, buf);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::cli::Cli;
+use crate::keymap::Keymap;
+use crate::location::Location;
+use crate::paths;
+use crate::provider::ProviderKind;
+use crate::theme::Theme;
+use crate::units::{PressureUnit, TemperatureUnit, UnitSystem, Units, WindUnit};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+// Polling OpenWeatherMap more often than this only burns through the free quota,
+// the data itself updates roughly every ten minutes
+const MIN_REFRESH_INTERVAL: u64 = 60;
+const DEFAULT_REFRESH_INTERVAL: u64 = 600;
+const DEFAULT_CACHE_TTL: u64 = 300;
+
+// Contents of `config.toml`, every key is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub provider: Option<ProviderKind>,
+    pub api_key: Option<String>,
+    pub default_city: Option<Location>,
+    pub units: Option<UnitSystem>,
+    pub temperature_unit: Option<TemperatureUnit>,
+    pub wind_unit: Option<WindUnit>,
+    pub pressure_unit: Option<PressureUnit>,
+    pub language: Option<String>,
+    pub base_url: Option<String>,
+    pub refresh_interval: Option<u64>, // seconds
+    pub cache_ttl: Option<u64>,        // seconds
+    pub theme: Theme,
+    pub keybindings: Keymap,
+}
+
+impl Config {
+    // Read the config from `path`, or from the XDG location when no path is given.
+    // Only an explicitly requested file has to exist.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match paths::config_file() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+        };
+        toml::from_str(&text).map_err(|e| format!("invalid config {}: {}", path.display(), e))
+    }
+}
+
+// Settings after merging the sources, command line flags win over environment variables,
+// which win over the config file, which wins over the defaults
+#[derive(Debug)]
+pub struct Settings {
+    pub provider: ProviderKind,
+    pub api_key: Option<String>,
+    pub language: Option<String>,
+    // replaces the scheme and host of the weather service, `None` keeps the public one
+    pub base_url: Option<String>,
+    pub default_city: Option<Location>,
+    // how often the TUI refetches the shown location
+    pub refresh_interval: Duration,
+    // cached responses younger than this are used without asking the service
+    pub cache_ttl: Duration,
+    pub offline: bool,
+    pub units: Units,
+    pub theme: Theme,
+    pub keybindings: Keymap,
+}
+
+impl Settings {
+    pub fn resolve(cli: &Cli, config: Config) -> Result<Self, String> {
+        let api_key = env("OPEN_WEATHER_API_KEY").or(config.api_key);
+
+        // without an explicit choice use OpenWeatherMap if there is a key for it and fall
+        // back to Open-Meteo, which works without one
+        let provider = match (cli.provider, env("TERMOCAST_PROVIDER"), config.provider) {
+            (Some(kind), _, _) => kind,
+            (None, Some(name), _) => name.parse()?,
+            (None, None, Some(kind)) => kind,
+            (None, None, None) if api_key.is_some() => ProviderKind::OpenWeatherMap,
+            (None, None, None) => ProviderKind::OpenMeteo,
+        };
+
+        let preset = Units::from(cli.units.or(config.units).unwrap_or(UnitSystem::Metric));
+        let units = Units {
+            temperature: cli
+                .temperature_unit
+                .or(config.temperature_unit)
+                .unwrap_or(preset.temperature),
+            wind: cli.wind_unit.or(config.wind_unit).unwrap_or(preset.wind),
+            pressure: cli
+                .pressure_unit
+                .or(config.pressure_unit)
+                .unwrap_or(preset.pressure),
+        };
+
+        let base_url = match cli
+            .base_url
+            .clone()
+            .or_else(|| env("TERMOCAST_BASE_URL"))
+            .or(config.base_url)
+        {
+            Some(url) if url.starts_with("https://") || url.starts_with("http://") => {
+                Some(url.trim_end_matches('/').to_string())
+            }
+            Some(url) => {
+                return Err(format!(
+                    "base URL '{}' must start with https:// or http://",
+                    url
+                ));
+            }
+            None => None,
+        };
+
+        let refresh_interval = config.refresh_interval.unwrap_or(DEFAULT_REFRESH_INTERVAL);
+        if refresh_interval < MIN_REFRESH_INTERVAL {
+            return Err(format!(
+                "refresh_interval must be at least {} seconds",
+                MIN_REFRESH_INTERVAL
+            ));
+        }
+
+        Ok(Self {
+            provider,
+            api_key,
+            language: cli.language.clone().or(config.language),
+            base_url,
+            default_city: config.default_city,
+            refresh_interval: Duration::from_secs(refresh_interval),
+            cache_ttl: Duration::from_secs(config.cache_ttl.unwrap_or(DEFAULT_CACHE_TTL)),
+            offline: cli.offline,
+            units,
+            theme: config.theme,
+            keybindings: config.keybindings,
+        })
+    }
+}
+
+// Empty variables count as unset so `OPEN_WEATHER_API_KEY=` does not hide the config value
+fn env(name: &str) -> Option<String> {
+    dotenv::var(name).ok().filter(|value| !value.is_empty())
+}
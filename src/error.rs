@@ -0,0 +1,90 @@
+use std::fmt;
+
+// Everything that can go wrong between typing a city and getting a reading back
+#[derive(Debug)]
+pub enum TermocastError {
+    MissingApiKey,
+    Network(reqwest::Error),
+    // the service answered with an error status, `message` is what it said about it
+    Http {
+        status: reqwest::StatusCode,
+        message: String,
+    },
+    Parse(serde_json::Error),
+    CityNotFound(String),
+    // typed input that is not a location, e.g. `id:abc`
+    InvalidLocation(String),
+    // offline mode and nothing in the cache for this location
+    NotCached(String),
+}
+
+impl fmt::Display for TermocastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingApiKey => write!(
+                f,
+                "No OpenWeatherMap API key. Set OPEN_WEATHER_API_KEY or api_key in config.toml, or use the open-meteo provider."
+            ),
+            Self::Network(e) => write!(f, "Network error: {}", e),
+            Self::Http { status, message } => write!(f, "Request failed ({}): {}", status, message),
+            Self::Parse(e) => write!(f, "Could not read the weather data: {}", e),
+            Self::CityNotFound(city) => write!(f, "City '{}' not found.", city),
+            Self::InvalidLocation(message) => write!(f, "Invalid location: {}", message),
+            Self::NotCached(place) => {
+                write!(
+                    f,
+                    "No cached weather for '{}' and offline mode is on.",
+                    place
+                )
+            }
+        }
+    }
+}
+
+impl TermocastError {
+    // Process exit status for the one-shot CLI mode. 1 is left for other failures and
+    // 2 for invalid arguments, which is what clap exits with.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::CityNotFound(_) => 3,
+            Self::MissingApiKey => 4,
+            Self::Network(_) => 5,
+            Self::Http { .. } => 6,
+            Self::Parse(_) => 7,
+            Self::InvalidLocation(_) => 2,
+            Self::NotCached(_) => 8,
+        }
+    }
+
+    // The service could not be reached or failed on its side, as opposed to answering
+    // that the request itself was wrong. Cached data is good enough for these.
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            Self::Network(_) => true,
+            Self::Http { status, .. } => status.is_server_error(),
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for TermocastError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(e) => Some(e),
+            Self::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for TermocastError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Network(e)
+    }
+}
+
+impl From<serde_json::Error> for TermocastError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parse(e)
+    }
+}
@@ -0,0 +1,104 @@
+use crate::location::Location;
+use crate::paths;
+use crate::types::WeatherDetails;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+
+// Places closer than this (in degrees, roughly 1 km) are treated as the same favorite
+const SAME_PLACE: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Favorite {
+    pub name: String,
+    pub country: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Favorite {
+    pub fn from_details(details: &WeatherDetails) -> Self {
+        Self {
+            name: details.name.clone(),
+            country: details.sys.country.clone(),
+            lat: details.coord.lat,
+            lon: details.coord.lon,
+        }
+    }
+
+    // Favorites are fetched by coordinates so they always resolve to the saved place
+    pub fn location(&self) -> Location {
+        Location::Coordinates {
+            lat: self.lat,
+            lon: self.lon,
+            name: Some(self.name.clone()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match &self.country {
+            Some(country) => format!("{}, {}", self.name, country),
+            None => self.name.clone(),
+        }
+    }
+}
+
+// Saved locations, kept in `$XDG_DATA_HOME/termocast/favorites.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Favorites {
+    #[serde(default, rename = "favorite")]
+    pub entries: Vec<Favorite>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Favorites {
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = paths::data_dir().map(|dir| dir.join("favorites.toml")) else {
+            return Ok(Self::default());
+        };
+
+        let mut favorites: Self = match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .map_err(|e| format!("invalid favorites file {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+        };
+        favorites.path = Some(path);
+        Ok(favorites)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        std::fs::write(path, text)
+    }
+
+    // Add a favorite unless the same place is already saved, returns its index either way
+    pub fn add(&mut self, favorite: Favorite) -> usize {
+        if let Some(index) = self.entries.iter().position(|f| {
+            (f.lat - favorite.lat).abs() < SAME_PLACE && (f.lon - favorite.lon).abs() < SAME_PLACE
+        }) {
+            return index;
+        }
+        self.entries.push(favorite);
+        self.entries.len() - 1
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Favorite> {
+        (index < self.entries.len()).then(|| self.entries.remove(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,131 @@
+use crate::art::AsciiArt;
+use crate::format::art_condition;
+use crate::theme::Theme;
+use crate::types::{Forecast, ForecastEntry};
+use crate::units::Units;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    symbols::border,
+    text::{Line, Text},
+    widgets::{Block, Paragraph, Widget},
+};
+use std::collections::HashMap;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+// 1970-01-01 was a Thursday
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+#[derive(Debug)]
+pub struct DailySummary {
+    pub weekday: &'static str,
+    pub temp_min: f32,
+    pub temp_max: f32,
+    pub condition: String,
+    pub art_condition: String,
+}
+
+// Group the 3 hour slots by local calendar day. The dominant condition is the one
+// reported in most slots of that day, ties go to whichever showed up first.
+pub fn daily_summaries(forecast: &Forecast) -> Vec<DailySummary> {
+    let mut days: Vec<(i64, Vec<&ForecastEntry>)> = Vec::new();
+    for entry in &forecast.list {
+        let day = (entry.dt as i64 + forecast.city.timezone).div_euclid(SECONDS_PER_DAY);
+        match days.last_mut() {
+            Some((last, entries)) if *last == day => entries.push(entry),
+            _ => days.push((day, vec![entry])),
+        }
+    }
+
+    days.into_iter()
+        .map(|(day, entries)| {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            let mut order = Vec::new();
+            for entry in &entries {
+                let description = entry
+                    .weather
+                    .first()
+                    .map_or("N/A", |w| w.description.as_str());
+                let count = counts.entry(description).or_default();
+                if *count == 0 {
+                    order.push(description);
+                }
+                *count += 1;
+            }
+            let condition = order
+                .iter()
+                .copied()
+                .reduce(|best, c| if counts[c] > counts[best] { c } else { best })
+                .unwrap_or("N/A");
+
+            DailySummary {
+                weekday: WEEKDAYS[day.rem_euclid(7) as usize],
+                temp_min: entries
+                    .iter()
+                    .map(|e| e.main.temp_min)
+                    .fold(f32::INFINITY, f32::min),
+                temp_max: entries
+                    .iter()
+                    .map(|e| e.main.temp_max)
+                    .fold(f32::NEG_INFINITY, f32::max),
+                art_condition: entries
+                    .iter()
+                    .filter_map(|e| e.weather.first())
+                    .find(|w| w.description == condition)
+                    .map_or(String::new(), art_condition),
+                condition: condition.to_string(),
+            }
+        })
+        .collect()
+}
+
+const ICON_HEIGHT: usize = 5;
+
+// Centering works line by line, so pad the icon into a block of equal width lines
+// to keep its shape intact
+fn icon_lines(icon: &str) -> Vec<Line<'static>> {
+    let width = icon.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    icon.lines()
+        .chain(std::iter::repeat(""))
+        .take(ICON_HEIGHT)
+        .map(|l| Line::from(format!("{:<width$}", l, width = width)))
+        .collect()
+}
+
+// One column per day with the icon, dominant condition and min/max temperature
+pub struct ForecastPanel<'a> {
+    pub forecast: &'a Forecast,
+    pub art: &'a AsciiArt,
+    pub units: &'a Units,
+    pub theme: &'a Theme,
+}
+
+impl Widget for ForecastPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(Line::from(" Forecast ").bold().centered())
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(self.theme.accent));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let days = daily_summaries(self.forecast);
+        if days.is_empty() {
+            return;
+        }
+        let columns = Layout::horizontal(vec![Constraint::Fill(1); days.len()]).split(inner);
+
+        for (day, column) in days.iter().zip(columns.iter()) {
+            let mut text = Text::from(Line::from(day.weekday).bold());
+            text.extend(icon_lines(self.art.get_icon(&day.art_condition)));
+            text.push_line(Line::from(day.condition.as_str()).italic());
+            text.push_line(format!(
+                "{} / {}",
+                self.units.temperature_rounded(day.temp_max),
+                self.units.temperature_rounded(day.temp_min)
+            ));
+            Paragraph::new(text).centered().render(*column, buf);
+        }
+    }
+}
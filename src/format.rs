@@ -0,0 +1,63 @@
+use crate::art::AsciiArt;
+use crate::types::{WeatherCondition, WeatherDetails};
+use crate::units::Units;
+
+// Text block describing the current reading, shared by the TUI and the one-shot CLI mode.
+// The ascii art for the condition is put on top when `art` is given.
+pub fn format_reading(details: &WeatherDetails, art: Option<&AsciiArt>, units: &Units) -> String {
+    let description = details
+        .weather
+        .first()
+        .map_or("N/A", |w| w.description.as_str());
+
+    let header = art.map_or(String::new(), |art| {
+        let condition = details.weather.first().map_or(String::new(), art_condition);
+        format!("{}\n\n", art.get_art(&condition, is_day(details)))
+    });
+
+    format!(
+        "{}City: {}\nTemperature: {}\nMin Temp: {}\nMax Temp: {}\nHumidity: {}%\nPressure: {}\nWind Speed: {}\nWind Direction: {}°\nCloudiness: {}%\nDescription: {}\n",
+        header,
+        details.name,
+        units.temperature(details.main.temp),
+        units.temperature(details.main.temp_min),
+        units.temperature(details.main.temp_max),
+        details.main.humidity,
+        units.pressure(details.main.pressure as f32),
+        units.wind(details.wind.speed),
+        details.wind.deg,
+        details.clouds.all,
+        description
+    )
+}
+
+// Text to pick the ascii art with. The art lookup matches English words, so prefer the
+// group name over a description that may have been translated.
+pub fn art_condition(condition: &WeatherCondition) -> String {
+    if condition.main.is_empty() {
+        condition.description.clone()
+    } else {
+        condition.main.to_lowercase()
+    }
+}
+
+// How long ago a reading was taken, "just now", "12m ago" or "3h 5m ago"
+pub fn format_age(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => "just now".to_string(),
+        (0, m) => format!("{}m ago", m),
+        (h, m) => format!("{}h {}m ago", h, m),
+    }
+}
+
+pub fn is_day(details: &WeatherDetails) -> bool {
+    // adding the offset to convert to local time though it's not strictly necessary for this comparison
+    let current_time = details.dt as i64 + details.timezone;
+
+    let sunrise_time = details.sys.sunrise as i64 + details.timezone;
+
+    let sunset_time = details.sys.sunset as i64 + details.timezone;
+
+    current_time >= sunrise_time && current_time < sunset_time
+}
@@ -0,0 +1,111 @@
+use crate::paths;
+use std::io;
+use std::path::PathBuf;
+
+// Older searches are dropped once the history grows past this
+const MAX_ENTRIES: usize = 500;
+
+// Searched locations, oldest first, kept in `$XDG_DATA_HOME/termocast/history` with one
+// query per line. Also tracks where Up/Down browsing currently is.
+#[derive(Debug, Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+    // index of the entry shown while browsing, and what was typed before browsing started
+    position: Option<usize>,
+    draft: String,
+}
+
+impl History {
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = paths::data_dir().map(|dir| dir.join("history")) else {
+            return Ok(Self::default());
+        };
+
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(text) => text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("could not read {}: {}", path.display(), e)),
+        };
+        Ok(Self {
+            entries,
+            path: Some(path),
+            ..Self::default()
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut text = self.entries.join("\n");
+        text.push('\n');
+        std::fs::write(path, text)
+    }
+
+    // Record a query as the newest entry, an earlier identical one is moved rather than
+    // repeated
+    pub fn add(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.entries
+            .retain(|entry| !entry.eq_ignore_ascii_case(query));
+        self.entries.push(query.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+        self.reset_browsing();
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    // One step back in time. `current` is what the input holds, it is given back by
+    // `newer` once browsing goes past the newest entry.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(position) => position.saturating_sub(1),
+        };
+        self.position = Some(position);
+        Some(&self.entries[position])
+    }
+
+    pub fn newer(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(&self.entries[position + 1])
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+
+    // Editing the recalled text ends browsing, the next Up starts from the newest again
+    pub fn reset_browsing(&mut self) {
+        self.position = None;
+    }
+
+    // Newest entry containing `query` that is older than `before`, ignoring case
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        let query = query.to_lowercase();
+        self.entries[..before.min(self.entries.len())]
+            .iter()
+            .rposition(|entry| entry.to_lowercase().contains(&query))
+    }
+}
@@ -0,0 +1,357 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Single line text field with the usual readline style editing. The cursor always sits
+// on a grapheme boundary, so "é" written as e + combining accent or a flag emoji are
+// moved over and deleted as one character, and positions on screen go by display
+// width so wide characters like "東" take the two columns they are drawn in.
+#[derive(Debug)]
+pub struct TextInput {
+    value: String,
+    cursor: usize, // byte offset into `value`
+    scroll: usize, // byte offset of the first visible grapheme
+    width: usize,  // columns available on screen
+}
+
+impl TextInput {
+    pub fn new(width: usize) -> Self {
+        Self {
+            value: String::new(),
+            cursor: 0,
+            scroll: 0,
+            width,
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    // Replace the text and put the cursor at its end
+    pub fn set(&mut self, value: &str) {
+        self.value = value.to_string();
+        self.cursor = self.value.len();
+        self.scroll = 0;
+        self.adjust_scroll();
+    }
+
+    pub fn clear(&mut self) {
+        self.set("");
+    }
+
+    // Apply an editing key, returns false for keys the input does not use
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.value.len(),
+            KeyCode::Char('w') if ctrl => self.delete_word_before(),
+            KeyCode::Char('u') if ctrl => {
+                self.value.replace_range(..self.cursor, "");
+                self.cursor = 0;
+            }
+            KeyCode::Char('b') if alt => self.cursor = self.word_start_before(),
+            KeyCode::Char('f') if alt => self.cursor = self.word_end_after(),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => {
+                self.value.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            KeyCode::Left if ctrl || alt => self.cursor = self.word_start_before(),
+            KeyCode::Right if ctrl || alt => self.cursor = self.word_end_after(),
+            KeyCode::Left => self.cursor = self.previous_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.value.len(),
+            KeyCode::Backspace => {
+                let start = self.previous_boundary();
+                self.value.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            KeyCode::Delete => {
+                let end = self.next_boundary();
+                self.value.replace_range(self.cursor..end, "");
+            }
+            _ => return false,
+        }
+        self.adjust_scroll();
+        true
+    }
+
+    // The part of the text that fits the box, starting at the scroll position
+    pub fn visible(&self) -> &str {
+        let mut used = 0;
+        let mut end = self.scroll;
+        for grapheme in self.value[self.scroll..].graphemes(true) {
+            used += grapheme.width();
+            if used > self.width {
+                break;
+            }
+            end += grapheme.len();
+        }
+        &self.value[self.scroll..end]
+    }
+
+    // Screen column of the cursor relative to the start of the box
+    pub fn cursor_column(&self) -> u16 {
+        self.value[self.scroll..self.cursor].width() as u16
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.value[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(index, _)| index)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.value[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    // Words are runs of letters and digits, so "São Paulo,BR" has three
+    fn word_start_before(&self) -> usize {
+        let mut graphemes = self.value[..self.cursor].grapheme_indices(true).rev();
+        let mut start = self.cursor;
+        let mut in_word = false;
+        for (index, grapheme) in graphemes.by_ref() {
+            let word = is_word(grapheme);
+            if in_word && !word {
+                break;
+            }
+            in_word |= word;
+            start = index;
+        }
+        start
+    }
+
+    fn word_end_after(&self) -> usize {
+        let mut end = self.cursor;
+        let mut in_word = false;
+        for grapheme in self.value[self.cursor..].graphemes(true) {
+            let word = is_word(grapheme);
+            if in_word && !word {
+                break;
+            }
+            in_word |= word;
+            end += grapheme.len();
+        }
+        end
+    }
+
+    fn delete_word_before(&mut self) {
+        let start = self.word_start_before();
+        self.value.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    // Keep the cursor inside the box, leaving a column for it past the last character
+    fn adjust_scroll(&mut self) {
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        }
+        while self.value[self.scroll..self.cursor].width() >= self.width.max(1) {
+            match self.value[self.scroll..].graphemes(true).next() {
+                Some(grapheme) => self.scroll += grapheme.len(),
+                None => break,
+            }
+        }
+        // after deleting, bring back text from the left while there is room for it
+        while let Some((index, _)) = self.value[..self.scroll].grapheme_indices(true).next_back()
+            && self.value[index..self.cursor].width() < self.width
+        {
+            self.scroll = index;
+        }
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // same as the city box, 20 columns minus its border
+    const WIDTH: usize = 18;
+
+    fn press(input: &mut TextInput, code: KeyCode) -> bool {
+        input.handle_key(&KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn ctrl(input: &mut TextInput, code: KeyCode) -> bool {
+        input.handle_key(&KeyEvent::new(code, KeyModifiers::CONTROL))
+    }
+
+    fn alt(input: &mut TextInput, code: KeyCode) -> bool {
+        input.handle_key(&KeyEvent::new(code, KeyModifiers::ALT))
+    }
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::new(WIDTH);
+        for c in text.chars() {
+            press(&mut input, KeyCode::Char(c));
+        }
+        input
+    }
+
+    #[test]
+    fn combining_marks_are_one_character() {
+        // "é" written as e followed by a combining acute accent
+        let mut input = typed("Sae\u{301}b");
+        press(&mut input, KeyCode::Left);
+        press(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor_column(), 2);
+        press(&mut input, KeyCode::Delete);
+        assert_eq!(input.value(), "Sab");
+
+        let mut input = typed("Jose\u{301}");
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.value(), "Jos");
+    }
+
+    #[test]
+    fn flag_emoji_are_one_character() {
+        let mut input = typed("Nepal 🇳🇵");
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.value(), "Nepal ");
+
+        let mut input = typed("🇳🇵x");
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::Right);
+        press(&mut input, KeyCode::Delete);
+        assert_eq!(input.value(), "🇳🇵");
+    }
+
+    #[test]
+    fn wide_characters_take_two_columns() {
+        let mut input = typed("東京");
+        assert_eq!(input.cursor_column(), 4);
+        press(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor_column(), 2);
+        press(&mut input, KeyCode::Char('a'));
+        assert_eq!(input.value(), "東a京");
+        assert_eq!(input.cursor_column(), 3);
+    }
+
+    #[test]
+    fn ctrl_w_deletes_the_word_before_the_cursor() {
+        let mut input = typed("São Paulo");
+        ctrl(&mut input, KeyCode::Char('w'));
+        assert_eq!(input.value(), "São ");
+        ctrl(&mut input, KeyCode::Char('w'));
+        assert_eq!(input.value(), "");
+    }
+
+    #[test]
+    fn ctrl_u_deletes_up_to_the_cursor() {
+        let mut input = typed("Kathmandu");
+        for _ in 0..3 {
+            press(&mut input, KeyCode::Left);
+        }
+        ctrl(&mut input, KeyCode::Char('u'));
+        assert_eq!(input.value(), "ndu");
+        assert_eq!(input.cursor_column(), 0);
+    }
+
+    #[test]
+    fn ctrl_a_and_ctrl_e_go_to_the_ends() {
+        let mut input = typed("Lima");
+        ctrl(&mut input, KeyCode::Char('a'));
+        press(&mut input, KeyCode::Char('>'));
+        ctrl(&mut input, KeyCode::Char('e'));
+        press(&mut input, KeyCode::Char('<'));
+        assert_eq!(input.value(), ">Lima<");
+    }
+
+    #[test]
+    fn word_jumps_stop_at_punctuation() {
+        let mut input = typed("São Paulo,BR");
+        press(&mut input, KeyCode::Home);
+
+        alt(&mut input, KeyCode::Char('f'));
+        assert_eq!(input.cursor_column(), 3);
+        ctrl(&mut input, KeyCode::Right);
+        assert_eq!(input.cursor_column(), 9);
+        alt(&mut input, KeyCode::Right);
+        assert_eq!(input.cursor_column(), 12);
+
+        ctrl(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor_column(), 10);
+        alt(&mut input, KeyCode::Char('b'));
+        assert_eq!(input.cursor_column(), 4);
+        alt(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor_column(), 0);
+    }
+
+    #[test]
+    fn delete_at_the_end_changes_nothing() {
+        let mut input = typed("Oslo");
+        assert!(press(&mut input, KeyCode::Delete));
+        assert_eq!(input.value(), "Oslo");
+        assert_eq!(input.cursor_column(), 4);
+    }
+
+    #[test]
+    fn modified_letters_are_not_typed() {
+        let mut input = typed("Rome");
+        assert!(!ctrl(&mut input, KeyCode::Char('x')));
+        assert!(!alt(&mut input, KeyCode::Char('1')));
+        assert!(!press(&mut input, KeyCode::F(2)));
+        assert_eq!(input.value(), "Rome");
+    }
+
+    #[test]
+    fn long_input_scrolls_to_keep_the_cursor_visible() {
+        let mut input = typed("abcdefghijklmnopqrstuvwxy");
+        // one column stays free for the cursor after the last character
+        assert_eq!(input.visible(), "ijklmnopqrstuvwxy");
+        assert_eq!(input.cursor_column(), 17);
+
+        press(&mut input, KeyCode::Home);
+        assert_eq!(input.visible(), "abcdefghijklmnopqr");
+        assert_eq!(input.cursor_column(), 0);
+
+        press(&mut input, KeyCode::End);
+        assert_eq!(input.visible(), "ijklmnopqrstuvwxy");
+    }
+
+    #[test]
+    fn deleting_scrolls_text_back_in() {
+        let mut input = typed("abcdefghijklmnopqrstuvwxy");
+        for _ in 0..5 {
+            press(&mut input, KeyCode::Backspace);
+        }
+        assert_eq!(input.value(), "abcdefghijklmnopqrst");
+        assert_eq!(input.visible(), "defghijklmnopqrst");
+        assert_eq!(input.cursor_column(), 17);
+    }
+
+    #[test]
+    fn wide_characters_scroll_by_whole_characters() {
+        let input = typed("東京東京東京東京東京");
+        assert!(input.visible().ends_with('京'));
+        assert_eq!(input.visible().width(), 16);
+        assert_eq!(input.cursor_column(), 16);
+    }
+
+    #[test]
+    fn set_puts_the_cursor_at_the_end() {
+        let mut input = TextInput::new(WIDTH);
+        input.set("Ho Chi Minh City, Vietnam");
+        assert_eq!(input.cursor_column(), 17);
+        assert!(input.visible().ends_with("Vietnam"));
+        input.clear();
+        assert!(input.is_empty());
+        assert_eq!(input.cursor_column(), 0);
+    }
+}
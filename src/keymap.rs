@@ -0,0 +1,616 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// What a binding listens for. `digit` stands for any of 1 to 9, the keys that jump to
+// the favorite or sort by the column with that number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Code(KeyCode),
+    Digit,
+}
+
+impl Key {
+    fn matches(self, code: KeyCode) -> bool {
+        match self {
+            Self::Code(own) => own == code,
+            Self::Digit => matches!(code, KeyCode::Char('1'..='9')),
+        }
+    }
+}
+
+// A key with its modifiers, written as `esc`, `f2`, `ctrl+s`, `alt+up` or `alt+digit`
+// in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    pub key: Key,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(key: Key, modifiers: KeyModifiers) -> Self {
+        Self { key, modifiers }
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        // terminals disagree on whether shifted characters carry SHIFT, the character itself is enough
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+            _ => key.modifiers,
+        };
+        self.key.matches(key.code) && self.modifiers == modifiers
+    }
+
+    // The display form with `digit` covering only 1 to `last`, for hints where the
+    // higher digits do nothing
+    pub fn label(&self, last: usize) -> String {
+        let label = self.to_string();
+        match self.key {
+            Key::Digit => label.replace("1..9", &format!("1..{}", last)),
+            Key::Code(_) => label,
+        }
+    }
+
+    // A character without modifiers, which while typing belongs in the city input
+    fn is_text(&self) -> bool {
+        self.modifiers - KeyModifiers::SHIFT == KeyModifiers::NONE
+            && matches!(self.key, Key::Digit | Key::Code(KeyCode::Char(_)))
+    }
+
+    // Whether a key press could match both, `digit` covers `1` and the rest
+    fn overlaps(&self, other: &Self) -> bool {
+        let keys = match (self.key, other.key) {
+            (Key::Digit, Key::Code(code)) | (Key::Code(code), Key::Digit) => {
+                Key::Digit.matches(code)
+            }
+            (own, other) => own == other,
+        };
+        keys && self.modifiers == other.modifiers
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key binding '{}'", s);
+        let mut parts: Vec<&str> = s.split('+').collect();
+        // `ctrl++` binds the plus key
+        if s.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let key = parts.pop().filter(|k| !k.is_empty()).ok_or_else(invalid)?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let lower = key.to_ascii_lowercase();
+        let code = match lower.as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            "digit" => return Ok(Self::new(Key::Digit, modifiers)),
+            f if f.len() > 1 && f.starts_with('f') => {
+                KeyCode::F(f[1..].parse().map_err(|_| invalid())?)
+            }
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        Ok(Self::new(Key::Code(code), modifiers))
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        let code = match self.key {
+            Key::Code(code) => code,
+            Key::Digit => return f.write_str("1..9"),
+        };
+        match code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Up => f.write_str("Up"),
+            KeyCode::Down => f.write_str("Down"),
+            KeyCode::Left => f.write_str("Left"),
+            KeyCode::Right => f.write_str("Right"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+// Everything a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Help,
+    Navigate,
+    Type,
+    Search,
+    AcceptSuggestion,
+    HistoryOlder,
+    HistoryNewer,
+    HistorySearch,
+    CycleUnits,
+    SaveFavorite,
+    RemoveFavorite,
+    PreviousFavorite,
+    NextFavorite,
+    JumpFavorite,
+    Compare,
+    SortColumn,
+    Refresh,
+}
+
+impl Action {
+    // The name in the config file
+    fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Help => "help",
+            Self::Navigate => "navigate",
+            Self::Type => "type",
+            Self::Search => "search",
+            Self::AcceptSuggestion => "accept_suggestion",
+            Self::HistoryOlder => "history_older",
+            Self::HistoryNewer => "history_newer",
+            Self::HistorySearch => "history_search",
+            Self::CycleUnits => "cycle_units",
+            Self::SaveFavorite => "save_favorite",
+            Self::RemoveFavorite => "remove_favorite",
+            Self::PreviousFavorite => "previous_favorite",
+            Self::NextFavorite => "next_favorite",
+            Self::JumpFavorite => "jump_favorite",
+            Self::Compare => "compare",
+            Self::SortColumn => "sort_column",
+            Self::Refresh => "refresh",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Quit => "Quit",
+            Self::Help => "Show this help",
+            Self::Navigate => "Leave the city input",
+            Self::Type => "Type a city",
+            Self::Search => "Search for the city",
+            Self::AcceptSuggestion => "Take the suggestion",
+            Self::HistoryOlder => "Previous suggestion or older search",
+            Self::HistoryNewer => "Next suggestion or newer search",
+            Self::HistorySearch => "Search the history",
+            Self::CycleUnits => "Switch units",
+            Self::SaveFavorite => "Save favorite",
+            Self::RemoveFavorite => "Remove favorite",
+            Self::PreviousFavorite => "Previous favorite",
+            Self::NextFavorite => "Next favorite",
+            Self::JumpFavorite => "Jump to a favorite",
+            Self::Compare => "Compare favorites",
+            Self::SortColumn => "Sort the comparison, again to reverse",
+            Self::Refresh => "Refresh",
+        }
+    }
+}
+
+// While typing, plain characters go to the city input and only keys with modifiers or
+// function keys trigger actions. In navigation mode single letters work as well. The
+// place picker and the history search go by the typing keys of `search`, `navigate`,
+// `history_older`, `history_newer` and `history_search`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Typing,
+    Navigation,
+}
+
+// Keys per action in each mode, the `[keybindings]` table of the config file. Actions
+// listed directly in the table are rebound in both modes, the `typing` and `navigation`
+// subtables rebind them in one mode only. Rebinding an action replaces its default keys
+// in that mode, and a result where one key does two things in a mode, or a plain
+// character is a command while typing, is rejected.
+//
+// The `comparison` subtable holds keys that go first in navigation mode while the
+// comparison is shown, which is how plain digits sort it there instead of jumping to
+// favorites.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "KeymapConfig")]
+pub struct Keymap {
+    typing: BTreeMap<Action, Vec<KeyBinding>>,
+    navigation: BTreeMap<Action, Vec<KeyBinding>>,
+    comparison: BTreeMap<Action, Vec<KeyBinding>>,
+}
+
+fn find(keys: &BTreeMap<Action, Vec<KeyBinding>>, key: &KeyEvent) -> Option<Action> {
+    keys.iter()
+        .find(|(_, keys)| keys.iter().any(|binding| binding.matches(key)))
+        .map(|(action, _)| *action)
+}
+
+impl Keymap {
+    fn mode(&self, mode: Mode) -> &BTreeMap<Action, Vec<KeyBinding>> {
+        match mode {
+            Mode::Typing => &self.typing,
+            Mode::Navigation => &self.navigation,
+        }
+    }
+
+    pub fn action(&self, mode: Mode, key: &KeyEvent) -> Option<Action> {
+        find(self.mode(mode), key)
+    }
+
+    // The action of a key while the comparison is shown in navigation mode
+    pub fn comparison_action(&self, key: &KeyEvent) -> Option<Action> {
+        find(&self.comparison, key).or_else(|| self.action(Mode::Navigation, key))
+    }
+
+    // The first key for an action, the one hints show
+    pub fn key(&self, mode: Mode, action: Action) -> Option<KeyBinding> {
+        self.mode(mode)
+            .get(&action)
+            .and_then(|keys| keys.first())
+            .copied()
+    }
+
+    pub fn comparison_key(&self, action: Action) -> Option<KeyBinding> {
+        self.comparison
+            .get(&action)
+            .and_then(|keys| keys.first())
+            .copied()
+            .or_else(|| self.key(Mode::Navigation, action))
+    }
+
+    // The keys are already merged with the defaults, so a key the config took for one
+    // action while a default still has it for another shows up here
+    fn check(&self) -> Result<(), String> {
+        for (action, keys) in &self.typing {
+            if let Some(key) = keys.iter().find(|key| key.is_text()) {
+                return Err(format!(
+                    "'{}' for {} would be typed into the city input, bind it under [keybindings.navigation] instead",
+                    key,
+                    action.name()
+                ));
+            }
+        }
+        // the comparison keys are meant to shadow navigation ones, only clashes among
+        // themselves count
+        for (keys, table) in [
+            (&self.typing, "typing"),
+            (&self.navigation, "navigation"),
+            (&self.comparison, "comparison"),
+        ] {
+            let bound: Vec<(Action, KeyBinding)> = keys
+                .iter()
+                .flat_map(|(action, keys)| keys.iter().map(|key| (*action, *key)))
+                .collect();
+            for (i, (action, key)) in bound.iter().enumerate() {
+                if let Some((other, _)) = bound[i + 1..]
+                    .iter()
+                    .find(|(other, other_key)| other != action && other_key.overlaps(key))
+                {
+                    return Err(format!(
+                        "'{}' is bound to both {} and {} in {} mode, rebind one of them under [keybindings.{}]",
+                        key,
+                        action.name(),
+                        other.name(),
+                        table,
+                        table
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Every bound action in the order of `Action`
+    pub fn bindings(&self, mode: Mode) -> impl Iterator<Item = (Action, &[KeyBinding])> {
+        self.mode(mode)
+            .iter()
+            .filter(|(_, keys)| !keys.is_empty())
+            .map(|(action, keys)| (*action, keys.as_slice()))
+    }
+
+    // What `bindings` gives while the comparison is shown, its keys merged with the
+    // navigation keys they leave over
+    pub fn comparison_bindings(&self) -> Vec<(Action, Vec<KeyBinding>)> {
+        let shadowed = |key: &KeyBinding| {
+            self.comparison
+                .values()
+                .flatten()
+                .any(|own| own.overlaps(key))
+        };
+        let mut bindings = self.comparison.clone();
+        for (action, keys) in &self.navigation {
+            bindings
+                .entry(*action)
+                .or_default()
+                .extend(keys.iter().filter(|key| !shadowed(key)));
+        }
+        bindings
+            .into_iter()
+            .filter(|(_, keys)| !keys.is_empty())
+            .collect()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action::*;
+        let keys = |keys: &[&str]| -> Vec<KeyBinding> {
+            keys.iter()
+                .map(|key| key.parse().expect("default key bindings are valid"))
+                .collect()
+        };
+        Self {
+            typing: BTreeMap::from([
+                (Quit, keys(&["ctrl+c"])),
+                (Help, keys(&["f1"])),
+                (Navigate, keys(&["esc"])),
+                (Search, keys(&["enter"])),
+                (AcceptSuggestion, keys(&["tab"])),
+                (HistoryOlder, keys(&["up"])),
+                (HistoryNewer, keys(&["down"])),
+                (HistorySearch, keys(&["ctrl+r"])),
+                (CycleUnits, keys(&["f2"])),
+                (SaveFavorite, keys(&["ctrl+s"])),
+                (RemoveFavorite, keys(&["ctrl+x"])),
+                (PreviousFavorite, keys(&["alt+up"])),
+                (NextFavorite, keys(&["alt+down"])),
+                (JumpFavorite, keys(&["alt+digit"])),
+                (Compare, keys(&["f3"])),
+                (Refresh, keys(&["f5"])),
+            ]),
+            navigation: BTreeMap::from([
+                (Quit, keys(&["esc", "q", "ctrl+c"])),
+                (Help, keys(&["?", "f1"])),
+                (Type, keys(&["i", "/", "enter"])),
+                (CycleUnits, keys(&["u", "f2"])),
+                (SaveFavorite, keys(&["s", "ctrl+s"])),
+                (RemoveFavorite, keys(&["x", "ctrl+x"])),
+                (PreviousFavorite, keys(&["up", "k", "alt+up"])),
+                (NextFavorite, keys(&["down", "j", "alt+down"])),
+                (JumpFavorite, keys(&["digit", "alt+digit"])),
+                (Compare, keys(&["c", "f3"])),
+                (Refresh, keys(&["r", "f5"])),
+            ]),
+            comparison: BTreeMap::from([(SortColumn, keys(&["digit"]))]),
+        }
+    }
+}
+
+// `quit = "esc"` or `quit = ["esc", "q"]`
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a key or a list of keys")]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn parse(self) -> Result<Vec<KeyBinding>, String> {
+        match self {
+            Self::One(key) => Ok(vec![key.parse()?]),
+            Self::Many(keys) => keys.iter().map(|key| key.parse()).collect(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct KeymapConfig {
+    typing: BTreeMap<Action, Keys>,
+    navigation: BTreeMap<Action, Keys>,
+    comparison: BTreeMap<Action, Keys>,
+    #[serde(flatten)]
+    both: BTreeMap<Action, Keys>,
+}
+
+impl TryFrom<KeymapConfig> for Keymap {
+    type Error = String;
+
+    fn try_from(config: KeymapConfig) -> Result<Self, Self::Error> {
+        let mut keymap = Self::default();
+        for (action, keys) in config.both {
+            let keys = keys.parse()?;
+            keymap.typing.insert(action, keys.clone());
+            keymap.navigation.insert(action, keys);
+        }
+        for (keys, bindings) in [
+            (&mut keymap.typing, config.typing),
+            (&mut keymap.navigation, config.navigation),
+            (&mut keymap.comparison, config.comparison),
+        ] {
+            for (action, binding) in bindings {
+                keys.insert(action, binding.parse()?);
+            }
+        }
+        keymap.check()?;
+        Ok(keymap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(config: &str) -> Result<Keymap, String> {
+        toml::from_str(config).map_err(|e| e.message().to_string())
+    }
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_keys() {
+        let binding: KeyBinding = "ctrl+alt+s".parse().unwrap();
+        assert_eq!(binding.key, Key::Code(KeyCode::Char('s')));
+        assert_eq!(binding.modifiers, KeyModifiers::CONTROL | KeyModifiers::ALT);
+        assert_eq!(
+            "ctrl++".parse::<KeyBinding>().unwrap().to_string(),
+            "Ctrl++"
+        );
+        assert_eq!("F12".parse::<KeyBinding>().unwrap().to_string(), "F12");
+        assert_eq!(
+            "alt+digit".parse::<KeyBinding>().unwrap().to_string(),
+            "Alt+1..9"
+        );
+        assert!("hyper+x".parse::<KeyBinding>().is_err());
+        assert!("fx".parse::<KeyBinding>().is_err());
+        assert!("ctrl+".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn digit_matches_one_to_nine() {
+        let binding: KeyBinding = "alt+digit".parse().unwrap();
+        assert!(binding.matches(&press(KeyCode::Char('1'), KeyModifiers::ALT)));
+        assert!(binding.matches(&press(KeyCode::Char('9'), KeyModifiers::ALT)));
+        assert!(!binding.matches(&press(KeyCode::Char('0'), KeyModifiers::ALT)));
+        assert!(!binding.matches(&press(KeyCode::Char('1'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn defaults_pass_the_check() {
+        assert_eq!(Keymap::default().check(), Ok(()));
+        assert!(keymap("").is_ok());
+    }
+
+    #[test]
+    fn letters_are_text_while_typing() {
+        let keys = Keymap::default();
+        let q = press(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(keys.action(Mode::Typing, &q), None);
+        assert_eq!(keys.action(Mode::Navigation, &q), Some(Action::Quit));
+    }
+
+    #[test]
+    fn top_level_entries_rebind_both_modes() {
+        let keys = keymap("compare = \"f4\"").unwrap();
+        let f4 = press(KeyCode::F(4), KeyModifiers::NONE);
+        assert_eq!(keys.action(Mode::Typing, &f4), Some(Action::Compare));
+        assert_eq!(keys.action(Mode::Navigation, &f4), Some(Action::Compare));
+        assert_eq!(
+            keys.action(Mode::Typing, &press(KeyCode::F(3), KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn plain_characters_are_rejected_while_typing() {
+        // the key would never reach the input, "Qatar" could not be typed
+        let error = keymap("quit = \"q\"").unwrap_err();
+        assert!(error.contains("'q' for quit would be typed"), "{}", error);
+        assert!(keymap("typing = { jump_favorite = \"digit\" }").is_err());
+        assert!(keymap("typing = { help = \"space\" }").is_err());
+        // fine with a modifier, and anywhere in navigation mode
+        assert!(keymap("typing = { quit = \"ctrl+q\" }").is_ok());
+        assert!(keymap("navigation = { compare = \"v\" }").is_ok());
+    }
+
+    #[test]
+    fn conflicts_with_defaults_are_reported() {
+        // an old config from before the modes, Esc already leaves the input
+        let error = keymap("quit = \"esc\"").unwrap_err();
+        assert!(
+            error.contains("'Esc' is bound to both quit and navigate in typing mode"),
+            "{}",
+            error
+        );
+        let error = keymap("navigation = { refresh = \"3\" }").unwrap_err();
+        assert!(error.contains("jump_favorite and refresh"), "{}", error);
+        // the conflict is gone once the other action moves
+        let keys = keymap("quit = \"esc\"\ntyping = { navigate = \"ctrl+n\" }").unwrap();
+        let esc = press(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(keys.action(Mode::Typing, &esc), Some(Action::Quit));
+        assert_eq!(keys.action(Mode::Navigation, &esc), Some(Action::Quit));
+    }
+
+    #[test]
+    fn comparison_keys_go_first() {
+        let keys = Keymap::default();
+        let two = press(KeyCode::Char('2'), KeyModifiers::NONE);
+        assert_eq!(keys.comparison_action(&two), Some(Action::SortColumn));
+        assert_eq!(
+            keys.action(Mode::Navigation, &two),
+            Some(Action::JumpFavorite)
+        );
+        let alt_two = press(KeyCode::Char('2'), KeyModifiers::ALT);
+        assert_eq!(keys.comparison_action(&alt_two), Some(Action::JumpFavorite));
+        let q = press(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(keys.comparison_action(&q), Some(Action::Quit));
+        assert_eq!(
+            keys.comparison_key(Action::SortColumn).unwrap().label(5),
+            "1..5"
+        );
+    }
+
+    #[test]
+    fn comparison_help_leaves_out_shadowed_keys() {
+        let bindings = Keymap::default().comparison_bindings();
+        let keys = |action| {
+            let (_, keys) = bindings.iter().find(|(a, _)| *a == action).unwrap();
+            keys.iter().map(|k| k.to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(Action::SortColumn), ["1..9"]);
+        assert_eq!(keys(Action::JumpFavorite), ["Alt+1..9"]);
+    }
+
+    #[test]
+    fn comparison_conflicts_are_reported() {
+        let error = keymap("comparison = { refresh = \"3\" }").unwrap_err();
+        assert!(
+            error.contains("sort_column and refresh in comparison mode"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn an_action_may_list_a_key_twice() {
+        assert!(keymap("navigation = { quit = [\"q\", \"q\"] }").is_ok());
+    }
+}
@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// What the user asked the weather for. Typed input goes through `FromStr`, which
+// understands `lat,lon`, `zip:94040,us` and `id:2643743` on top of plain names. In the
+// config file this is either such a string or a `{ lat = .., lon = .. }` table.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "LocationRepr")]
+pub enum Location {
+    // a place name, optionally narrowed down by country as in `London,GB`
+    Name(String),
+    Coordinates {
+        lat: f64,
+        lon: f64,
+        // label for places we already know, like saved favorites
+        name: Option<String>,
+    },
+    Zip {
+        code: String,
+        country: Option<String>,
+    },
+    // OpenWeatherMap city ID, these are GeoNames IDs so Open-Meteo knows them too
+    Id(u64),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LocationRepr {
+    Text(String),
+    Coordinates {
+        lat: f64,
+        lon: f64,
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
+
+impl TryFrom<LocationRepr> for Location {
+    type Error = String;
+
+    fn try_from(value: LocationRepr) -> Result<Self, Self::Error> {
+        match value {
+            LocationRepr::Text(text) => text.parse(),
+            LocationRepr::Coordinates { lat, lon, name } => {
+                Ok(Self::Coordinates { lat, lon, name })
+            }
+        }
+    }
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty location".to_string());
+        }
+
+        if let Some(zip) = strip_prefix_ignore_case(s, "zip:") {
+            let (code, country) = split_country(zip);
+            if code.is_empty() {
+                return Err(format!("missing postal code in '{}'", s));
+            }
+            return Ok(Self::Zip {
+                code: code.to_string(),
+                country: country.map(str::to_string),
+            });
+        }
+        if let Some(id) = strip_prefix_ignore_case(s, "id:") {
+            return id
+                .trim()
+                .parse()
+                .map(Self::Id)
+                .map_err(|_| format!("'{}' is not a city ID, expected a number", id.trim()));
+        }
+
+        // two numbers are coordinates, anything else is left to the geocoder
+        if let Some((lat, lon)) = s.split_once(',')
+            && let (Ok(lat), Ok(lon)) = (lat.trim().parse::<f64>(), lon.trim().parse::<f64>())
+        {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                return Err(format!(
+                    "coordinates out of range in '{}', latitude goes from -90 to 90 and longitude from -180 to 180",
+                    s
+                ));
+            }
+            return Ok(Self::Coordinates {
+                lat,
+                lon,
+                name: None,
+            });
+        }
+        Ok(Self::Name(s.to_string()))
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    s.get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &s[prefix.len()..])
+}
+
+// Split a trailing two letter country code off, `London,GB` gives `("London", Some("GB"))`
+pub fn split_country(s: &str) -> (&str, Option<&str>) {
+    match s.rsplit_once(',') {
+        Some((rest, country))
+            if country.trim().len() == 2
+                && country.trim().chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            (rest.trim(), Some(country.trim()))
+        }
+        _ => (s.trim(), None),
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::Coordinates {
+                name: Some(name), ..
+            } => f.write_str(name),
+            Self::Coordinates { lat, lon, .. } => write!(f, "{:.4}, {:.4}", lat, lon),
+            Self::Zip {
+                code,
+                country: Some(country),
+            } => write!(f, "{}, {}", code, country),
+            Self::Zip { code, .. } => f.write_str(code),
+            Self::Id(id) => write!(f, "city {}", id),
+        }
+    }
+}
+
+// One match of a geocoding search. State and country tell apart places sharing a name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    pub state: Option<String>,
+    pub country: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Place {
+    pub fn location(&self) -> Location {
+        Location::Coordinates {
+            lat: self.lat,
+            lon: self.lon,
+            name: Some(self.name.clone()),
+        }
+    }
+
+    // "Springfield, Illinois, US"
+    pub fn label(&self) -> String {
+        std::iter::once(self.name.as_str())
+            .chain(self.state.as_deref())
+            .chain(self.country.as_deref())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Location, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn names_are_left_to_the_geocoder() {
+        assert_eq!(parse(" London "), Ok(Location::Name("London".to_string())));
+        assert_eq!(
+            parse("London,GB"),
+            Ok(Location::Name("London,GB".to_string()))
+        );
+        // one number is not coordinates
+        assert_eq!(parse("12,abc"), Ok(Location::Name("12,abc".to_string())));
+        assert_eq!(parse("   "), Err("empty location".to_string()));
+    }
+
+    #[test]
+    fn two_numbers_are_coordinates() {
+        assert_eq!(
+            parse("27.7172, 85.324"),
+            Ok(Location::Coordinates {
+                lat: 27.7172,
+                lon: 85.324,
+                name: None,
+            })
+        );
+        assert_eq!(
+            parse("-90,180"),
+            Ok(Location::Coordinates {
+                lat: -90.0,
+                lon: 180.0,
+                name: None,
+            })
+        );
+    }
+
+    #[test]
+    fn coordinates_out_of_range_are_an_error() {
+        assert_eq!(
+            parse("91,0"),
+            Err("coordinates out of range in '91,0', latitude goes from -90 to 90 and longitude from -180 to 180".to_string())
+        );
+        assert!(parse("0,-180.5").is_err());
+    }
+
+    #[test]
+    fn zip_codes_take_an_optional_country() {
+        assert_eq!(
+            parse("zip:94040,us"),
+            Ok(Location::Zip {
+                code: "94040".to_string(),
+                country: Some("us".to_string()),
+            })
+        );
+        assert_eq!(
+            parse("ZIP:SW1A 1AA"),
+            Ok(Location::Zip {
+                code: "SW1A 1AA".to_string(),
+                country: None,
+            })
+        );
+        assert_eq!(
+            parse("zip:,us"),
+            Err("missing postal code in 'zip:,us'".to_string())
+        );
+    }
+
+    #[test]
+    fn ids_are_numbers() {
+        assert_eq!(parse("id:2643743"), Ok(Location::Id(2643743)));
+        assert_eq!(parse("Id: 42"), Ok(Location::Id(42)));
+        assert_eq!(
+            parse("id:abc"),
+            Err("'abc' is not a city ID, expected a number".to_string())
+        );
+    }
+
+    #[test]
+    fn split_country_wants_two_letters() {
+        assert_eq!(split_country("London, GB"), ("London", Some("GB")));
+        assert_eq!(split_country("Paris,fr"), ("Paris", Some("fr")));
+        assert_eq!(split_country("Paris,FRA"), ("Paris,FRA", None));
+        assert_eq!(
+            split_country("Washington, D.C."),
+            ("Washington, D.C.", None)
+        );
+        assert_eq!(split_country(" Lima "), ("Lima", None));
+    }
+
+    #[test]
+    fn config_tables_are_coordinates() {
+        let location: Location = toml::from_str::<toml::Value>("lat = 1.5\nlon = -2.0")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            location,
+            Location::Coordinates {
+                lat: 1.5,
+                lon: -2.0,
+                name: None,
+            }
+        );
+    }
+
+    #[test]
+    fn display_labels() {
+        assert_eq!(
+            parse("-33.86882,151.2093").unwrap().to_string(),
+            "-33.8688, 151.2093"
+        );
+        assert_eq!(parse("zip:94040,us").unwrap().to_string(), "94040, us");
+        assert_eq!(parse("id:7").unwrap().to_string(), "city 7");
+    }
+}
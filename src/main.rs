@@ -1,27 +1,72 @@
 mod art;
+mod cache;
+mod chart;
+mod cities;
+mod cli;
+mod compare;
+mod config;
+mod error;
+mod favorites;
+mod forecast;
+mod format;
+mod history;
+mod input;
+mod keymap;
+mod location;
+#[cfg(test)]
+mod mock_server;
+mod output;
+mod paths;
+mod provider;
+mod suggest;
+mod theme;
 mod types;
 mod ui;
+mod units;
+use cli::Cli;
+use config::{Config, Settings};
+use std::process::ExitCode;
 use ui::tui;
 
-// Function to fetch weather data from OpenWeatherMap API
-async fn fetch_weather(city: &str) -> reqwest::Response {
-    // Load API key from environment variable
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse_args();
+    // variables from a .env file in the working directory count as environment variables
     dotenv::dotenv().ok();
 
-    // this should fail if the key is not found
-    let key =
-        dotenv::var("OPEN_WEATHER_API_KEY").expect("API key not found in environment variables");
+    let settings = match Config::load(cli.config.as_deref())
+        .and_then(|config| Settings::resolve(&cli, config))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let provider = provider::build(&settings);
 
-    let base_url = "http://api.openweathermap.org/data/2.5/weather?";
-    let complete_url = format!("{}q={}&appid={}", base_url, city, key);
-    reqwest::get(&complete_url)
-        .await
-        .expect("Failed to send request")
-}
+    match cli.location() {
+        Ok(Some(location)) => {
+            return cli::run_once(
+                provider.as_ref(),
+                &location,
+                cli.format,
+                cli.art,
+                &settings.units,
+                settings.offline,
+            )
+            .await;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::from(e.exit_code());
+        }
+    }
 
-#[tokio::main]
-async fn main() {
-    if let Err(e) = tui() {
+    if let Err(e) = tui(provider, settings).await {
         println!("Error: {}", e);
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,248 @@
+use crate::art::AsciiArt;
+use crate::format::{format_reading, is_day};
+use crate::types::WeatherDetails;
+use crate::units::{TemperatureUnit, Units};
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Plain,
+    Json,
+    Yaml,
+    Csv,
+}
+
+// Provider independent view of a reading for machine consumers, with the values
+// scripts usually end up computing themselves already filled in
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub city: String,
+    pub description: String,
+    pub timestamp: u64,
+    pub timezone_offset: i64,
+    pub is_day: bool,
+    pub temperature: Temperature,
+    pub temperature_min: Temperature,
+    pub temperature_max: Temperature,
+    pub humidity: u8,
+    pub pressure_hpa: u16,
+    pub wind_speed_ms: f32,
+    pub wind_direction_deg: u16,
+    pub cloudiness: u8,
+    pub sunrise: u64,
+    pub sunset: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Temperature {
+    pub kelvin: f32,
+    pub celsius: f32,
+    pub fahrenheit: f32,
+}
+
+impl Temperature {
+    fn from_kelvin(kelvin: f32) -> Self {
+        Self {
+            kelvin: round2(kelvin),
+            celsius: round2(TemperatureUnit::Celsius.convert(kelvin)),
+            fahrenheit: round2(TemperatureUnit::Fahrenheit.convert(kelvin)),
+        }
+    }
+}
+
+fn round2(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+impl From<&WeatherDetails> for Report {
+    fn from(details: &WeatherDetails) -> Self {
+        Self {
+            city: details.name.clone(),
+            description: details
+                .weather
+                .first()
+                .map_or("N/A".to_string(), |w| w.description.clone()),
+            timestamp: details.dt,
+            timezone_offset: details.timezone,
+            is_day: is_day(details),
+            temperature: Temperature::from_kelvin(details.main.temp),
+            temperature_min: Temperature::from_kelvin(details.main.temp_min),
+            temperature_max: Temperature::from_kelvin(details.main.temp_max),
+            humidity: details.main.humidity,
+            pressure_hpa: details.main.pressure,
+            wind_speed_ms: details.wind.speed,
+            wind_direction_deg: details.wind.deg,
+            cloudiness: details.clouds.all,
+            sunrise: details.sys.sunrise,
+            sunset: details.sys.sunset,
+        }
+    }
+}
+
+// Render a reading in the requested format, `art` and `units` only apply to plain text,
+// the structured formats always carry every unit
+pub fn render(
+    details: &WeatherDetails,
+    format: OutputFormat,
+    art: Option<&AsciiArt>,
+    units: &Units,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let report = Report::from(details);
+    Ok(match format {
+        OutputFormat::Plain => format_reading(details, art, units),
+        OutputFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+        OutputFormat::Yaml => serde_yaml::to_string(&report)?,
+        OutputFormat::Csv => to_csv(&report),
+    })
+}
+
+// A header line and a single record, nested temperatures are flattened into
+// `temperature_celsius` style columns
+fn to_csv(report: &Report) -> String {
+    let mut columns: Vec<(String, String)> = vec![
+        ("city".into(), report.city.clone()),
+        ("description".into(), report.description.clone()),
+        ("timestamp".into(), report.timestamp.to_string()),
+        ("timezone_offset".into(), report.timezone_offset.to_string()),
+        ("is_day".into(), report.is_day.to_string()),
+    ];
+    for (name, temperature) in [
+        ("temperature", &report.temperature),
+        ("temperature_min", &report.temperature_min),
+        ("temperature_max", &report.temperature_max),
+    ] {
+        columns.push((format!("{}_kelvin", name), temperature.kelvin.to_string()));
+        columns.push((format!("{}_celsius", name), temperature.celsius.to_string()));
+        columns.push((
+            format!("{}_fahrenheit", name),
+            temperature.fahrenheit.to_string(),
+        ));
+    }
+    columns.extend([
+        ("humidity".into(), report.humidity.to_string()),
+        ("pressure_hpa".into(), report.pressure_hpa.to_string()),
+        ("wind_speed_ms".into(), report.wind_speed_ms.to_string()),
+        (
+            "wind_direction_deg".into(),
+            report.wind_direction_deg.to_string(),
+        ),
+        ("cloudiness".into(), report.cloudiness.to_string()),
+        ("sunrise".into(), report.sunrise.to_string()),
+        ("sunset".into(), report.sunset.to_string()),
+    ]);
+
+    let (header, record): (Vec<_>, Vec<_>) = columns
+        .into_iter()
+        .map(|(name, value)| (name, csv_field(&value)))
+        .unzip();
+    format!("{}\n{}\n", header.join(","), record.join(","))
+}
+
+// Quote a field when it contains a separator, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEATHER: &str = include_str!("../tests/fixtures/openweathermap/weather.json");
+
+    fn london() -> WeatherDetails {
+        serde_json::from_str(WEATHER).unwrap()
+    }
+
+    fn rendered(details: &WeatherDetails, format: OutputFormat) -> String {
+        render(details, format, None, &Units::default()).unwrap()
+    }
+
+    #[test]
+    fn csv_has_a_header_and_one_record() {
+        let csv = rendered(&london(), OutputFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "city,description,timestamp,timezone_offset,is_day,\
+                 temperature_kelvin,temperature_celsius,temperature_fahrenheit,\
+                 temperature_min_kelvin,temperature_min_celsius,temperature_min_fahrenheit,\
+                 temperature_max_kelvin,temperature_max_celsius,temperature_max_fahrenheit,\
+                 humidity,pressure_hpa,wind_speed_ms,wind_direction_deg,cloudiness,sunrise,sunset",
+                "London,light rain,1760782800,3600,true,\
+                 286.48,13.33,55.99,\
+                 285.37,12.22,54,\
+                 287.59,14.44,57.99,\
+                 82,1012,4.63,230,75,1760769347,1760806843",
+            ]
+        );
+        assert!(csv.ends_with('\n'));
+    }
+
+    #[test]
+    fn csv_quotes_commas_and_quotes() {
+        let mut details = london();
+        details.name = "Washington, D.C.".to_string();
+        details.weather[0].description = "rain, \"heavy\" at times".to_string();
+        let csv = rendered(&details, OutputFormat::Csv);
+        let record = csv.lines().nth(1).unwrap();
+        assert!(
+            record.starts_with("\"Washington, D.C.\",\"rain, \"\"heavy\"\" at times\",1760782800,"),
+            "{}",
+            record
+        );
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+        assert_eq!(csv_field("plain"), "plain");
+    }
+
+    #[test]
+    fn json_keeps_the_field_names() {
+        let json: serde_json::Value =
+            serde_json::from_str(&rendered(&london(), OutputFormat::Json)).unwrap();
+        let keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut expected = [
+            "city",
+            "description",
+            "timestamp",
+            "timezone_offset",
+            "is_day",
+            "temperature",
+            "temperature_min",
+            "temperature_max",
+            "humidity",
+            "pressure_hpa",
+            "wind_speed_ms",
+            "wind_direction_deg",
+            "cloudiness",
+            "sunrise",
+            "sunset",
+        ];
+        expected.sort();
+        assert_eq!(keys, expected);
+        assert_eq!(json["city"], "London");
+        assert_eq!(json["temperature"]["celsius"], 13.33);
+        assert_eq!(json["temperature"]["fahrenheit"], 55.99);
+        assert_eq!(json["temperature"]["kelvin"], 286.48);
+    }
+
+    #[test]
+    fn yaml_matches_json() {
+        let details = london();
+        let yaml: serde_json::Value =
+            serde_yaml::from_str(&rendered(&details, OutputFormat::Yaml)).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&rendered(&details, OutputFormat::Json)).unwrap();
+        assert_eq!(yaml, json);
+    }
+}
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+const APP_DIR: &str = "termocast";
+
+// `$XDG_CONFIG_HOME/termocast`, falling back to `~/.config/termocast`
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn config_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+// `$XDG_DATA_HOME/termocast`, falling back to `~/.local/share/termocast`
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+// `$XDG_CACHE_HOME/termocast`, falling back to `~/.cache/termocast`
+pub fn cache_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CACHE_HOME", ".cache")
+}
+
+// The XDG spec says relative paths in these variables are invalid and must be ignored
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    std::env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(fallback)))
+        .map(|base| base.join(APP_DIR))
+}
@@ -0,0 +1,134 @@
+mod open_meteo;
+mod openweathermap;
+
+use crate::cache::{Cache, CachedProvider};
+use crate::config::Settings;
+use crate::error::TermocastError;
+use crate::location::{Location, Place};
+use crate::paths;
+use crate::types::{Forecast, WeatherDetails};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+pub use open_meteo::OpenMeteo;
+pub use openweathermap::OpenWeatherMap;
+
+// A source of weather data. Every backend maps its own response format into
+// `WeatherDetails` so the UI never has to know which service it talks to.
+#[async_trait]
+pub trait WeatherProvider: std::fmt::Debug + Send + Sync {
+    // Fetch the current conditions for a location
+    async fn current(&self, location: &Location) -> Result<WeatherDetails, TermocastError>;
+
+    // Fetch the 5 day forecast in 3 hour steps for a location
+    async fn forecast(&self, location: &Location) -> Result<Forecast, TermocastError>;
+
+    // Look up the places matching a name, best match first. Finding nothing is a
+    // `CityNotFound` error so callers never get an empty list.
+    async fn geocode(&self, query: &str) -> Result<Vec<Place>, TermocastError>;
+
+    // Like `current` and `forecast`, for when the user or the refresh timer asks for new
+    // data. A cache in between has to ask the service again instead of answering with a
+    // copy it still considers fresh. Backends without one have nothing to skip.
+    async fn refresh_current(&self, location: &Location) -> Result<WeatherDetails, TermocastError> {
+        self.current(location).await
+    }
+
+    async fn refresh_forecast(&self, location: &Location) -> Result<Forecast, TermocastError> {
+        self.forecast(location).await
+    }
+}
+
+// How many candidates a geocoding search asks for
+pub const GEOCODE_LIMIT: usize = 5;
+
+// A request that takes longer than this, or cannot connect within the second one, fails
+// as a network error. A stalled connection would otherwise leave the fetch hanging for
+// the rest of the session, and the cache never gets to step in.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// One client for every backend, so they share the connection pool and the timeouts
+static CLIENT: LazyLock<reqwest::Client> =
+    LazyLock::new(|| client_with_timeouts(REQUEST_TIMEOUT, CONNECT_TIMEOUT));
+
+pub fn http_client() -> reqwest::Client {
+    // cloning shares the pool, the client is reference counted inside
+    CLIENT.clone()
+}
+
+fn client_with_timeouts(timeout: Duration, connect_timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(connect_timeout)
+        .build()
+        .expect("the HTTP client can be set up")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ProviderKind {
+    OpenWeatherMap,
+    OpenMeteo,
+}
+
+impl FromStr for ProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "openweathermap" | "owm" => Ok(Self::OpenWeatherMap),
+            "open-meteo" | "openmeteo" => Ok(Self::OpenMeteo),
+            other => Err(format!(
+                "unknown weather provider '{}', expected 'openweathermap' or 'open-meteo'",
+                other
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for ProviderKind {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl ProviderKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::OpenWeatherMap => "openweathermap",
+            Self::OpenMeteo => "open-meteo",
+        }
+    }
+}
+
+// Build the backend chosen in the settings, behind the response cache
+pub fn build(settings: &Settings) -> Arc<dyn WeatherProvider> {
+    let inner: Arc<dyn WeatherProvider> = match settings.provider {
+        ProviderKind::OpenWeatherMap => Arc::new(OpenWeatherMap {
+            api_key: settings.api_key.clone(),
+            language: settings.language.clone(),
+            base_url: settings
+                .base_url
+                .clone()
+                .unwrap_or_else(|| openweathermap::DEFAULT_BASE_URL.to_string()),
+            client: http_client(),
+        }),
+        ProviderKind::OpenMeteo => Arc::new(match &settings.base_url {
+            Some(base_url) => OpenMeteo::with_base_url(base_url),
+            None => OpenMeteo::default(),
+        }),
+    };
+    // one directory per backend, their responses are not interchangeable
+    let dir = paths::cache_dir().map(|dir| dir.join(settings.provider.name()));
+    Arc::new(CachedProvider {
+        inner,
+        cache: Cache::new(dir, settings.cache_ttl),
+        offline: settings.offline,
+    })
+}
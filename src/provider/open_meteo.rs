@@ -0,0 +1,603 @@
+use super::{GEOCODE_LIMIT, WeatherProvider, http_client};
+use crate::error::TermocastError;
+use crate::location::{Location, Place, split_country};
+use crate::types::{
+    CloudCover, Coord, Forecast, ForecastCity, ForecastEntry, MainReadings, Precipitation, SysInfo,
+    WeatherCondition, WeatherDetails, WindInfo,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_API_URL: &str = "https://api.open-meteo.com";
+pub const DEFAULT_GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com";
+
+const CURRENT_FIELDS: &str = "temperature_2m,relative_humidity_2m,pressure_msl,wind_speed_10m,wind_direction_10m,cloud_cover,weather_code";
+const DAILY_FIELDS: &str = "temperature_2m_max,temperature_2m_min,sunrise,sunset";
+const HOURLY_FIELDS: &str = "temperature_2m,relative_humidity_2m,pressure_msl,wind_speed_10m,wind_direction_10m,cloud_cover,weather_code,precipitation_probability,rain,snowfall";
+
+// OpenWeatherMap's forecast comes in 3 hour slots, hourly data is folded to match
+const SLOT_HOURS: usize = 3;
+
+// Open-Meteo is free for non-commercial use and does not need an API key
+#[derive(Debug)]
+pub struct OpenMeteo {
+    pub api_url: String,       // serves `/v1/forecast`
+    pub geocoding_url: String, // serves `/v1/search` and `/v1/get`
+    pub client: reqwest::Client,
+}
+
+impl Default for OpenMeteo {
+    fn default() -> Self {
+        Self {
+            api_url: DEFAULT_API_URL.to_string(),
+            geocoding_url: DEFAULT_GEOCODING_URL.to_string(),
+            client: http_client(),
+        }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenMeteo {
+    async fn current(&self, location: &Location) -> Result<WeatherDetails, TermocastError> {
+        let place = self.resolve(location).await?;
+        let forecast: ForecastResponse = get_json(self.client.get(self.forecast_url()).query(&[
+            ("latitude", place.latitude.to_string().as_str()),
+            ("longitude", place.longitude.to_string().as_str()),
+            ("current", CURRENT_FIELDS),
+            ("daily", DAILY_FIELDS),
+            ("timezone", "auto"),
+            ("timeformat", "unixtime"),
+            ("wind_speed_unit", "ms"),
+            ("forecast_days", "1"),
+        ]))
+        .await?;
+        Ok(to_weather_details(place, forecast))
+    }
+
+    async fn forecast(&self, location: &Location) -> Result<Forecast, TermocastError> {
+        let place = self.resolve(location).await?;
+        let forecast: HourlyForecastResponse =
+            get_json(self.client.get(self.forecast_url()).query(&[
+                ("latitude", place.latitude.to_string().as_str()),
+                ("longitude", place.longitude.to_string().as_str()),
+                ("hourly", HOURLY_FIELDS),
+                ("timezone", "auto"),
+                ("timeformat", "unixtime"),
+                ("wind_speed_unit", "ms"),
+                ("forecast_days", "5"),
+            ]))
+            .await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        to_forecast(place.name, forecast, now)
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Vec<Place>, TermocastError> {
+        let (name, country) = split_country(query);
+        Ok(self
+            .search(name, country, GEOCODE_LIMIT)
+            .await?
+            .into_iter()
+            .map(|result| Place {
+                name: result.name,
+                state: result.admin1,
+                country: result.country_code,
+                lat: result.latitude,
+                lon: result.longitude,
+            })
+            .collect())
+    }
+}
+
+// Open-Meteo's forecast endpoint only accepts lat/lon, names, postal codes and IDs go
+// through the geocoder first. Plain coordinates have no place name, so they are shown
+// as they were given.
+impl OpenMeteo {
+    // Point both services at one server, e.g. a caching proxy or a local stub. The
+    // forecast and geocoding paths do not overlap so a single host can serve them all.
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            api_url: base_url.to_string(),
+            geocoding_url: base_url.to_string(),
+            client: http_client(),
+        }
+    }
+
+    fn forecast_url(&self) -> String {
+        format!("{}/v1/forecast", self.api_url)
+    }
+
+    async fn resolve(&self, location: &Location) -> Result<GeocodingResult, TermocastError> {
+        match location {
+            Location::Name(city) => {
+                let (name, country) = split_country(city);
+                Ok(self.search(name, country, 1).await?.remove(0))
+            }
+            // the geocoder matches postal codes as well as names
+            Location::Zip { code, country } => {
+                Ok(self.search(code, country.as_deref(), 1).await?.remove(0))
+            }
+            Location::Id(id) => {
+                get_json(
+                    self.client
+                        .get(format!("{}/v1/get", self.geocoding_url))
+                        .query(&[("id", id.to_string())]),
+                )
+                .await
+            }
+            Location::Coordinates { lat, lon, .. } => Ok(GeocodingResult {
+                name: location.to_string(),
+                latitude: *lat,
+                longitude: *lon,
+                country_code: None,
+                admin1: None,
+            }),
+        }
+    }
+
+    // Never empty, no match is reported as `CityNotFound`
+    async fn search(
+        &self,
+        city: &str,
+        country: Option<&str>,
+        count: usize,
+    ) -> Result<Vec<GeocodingResult>, TermocastError> {
+        let mut request = self
+            .client
+            .get(format!("{}/v1/search", self.geocoding_url))
+            .query(&[("name", city), ("count", count.to_string().as_str())]);
+        if let Some(country) = country {
+            request = request.query(&[("countryCode", country.to_ascii_uppercase())]);
+        }
+        let response: GeocodingResponse = get_json(request).await?;
+        if response.results.is_empty() {
+            return Err(TermocastError::CityNotFound(city.to_string()));
+        }
+        Ok(response.results)
+    }
+}
+
+// Open-Meteo reports bad requests as `{"error":true,"reason":"..."}`
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    reason: String,
+}
+
+async fn get_json<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+) -> Result<T, TermocastError> {
+    let response = request.send().await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<ErrorBody>(&text).map_or(text, |body| body.reason);
+        return Err(TermocastError::Http { status, message });
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GeocodingResponse {
+    // the field is left out entirely when nothing matched
+    #[serde(default)]
+    pub results: Vec<GeocodingResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GeocodingResult {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub country_code: Option<String>,
+    // first level administrative area, the state or region
+    #[serde(default)]
+    pub admin1: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastResponse {
+    pub utc_offset_seconds: i64,
+    pub current: CurrentReadings,
+    pub daily: DailyReadings,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CurrentReadings {
+    pub time: u64,
+    pub temperature_2m: f32,
+    pub relative_humidity_2m: f32,
+    pub pressure_msl: f32,
+    pub wind_speed_10m: f32,
+    pub wind_direction_10m: f32,
+    pub cloud_cover: f32,
+    pub weather_code: u8,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyReadings {
+    pub temperature_2m_max: Vec<f32>,
+    pub temperature_2m_min: Vec<f32>,
+    pub sunrise: Vec<u64>,
+    pub sunset: Vec<u64>,
+}
+
+// Map an Open-Meteo response onto the OpenWeatherMap shaped structs the UI renders.
+// Temperatures are converted to kelvin since that is what OpenWeatherMap reports by default.
+pub fn to_weather_details(place: GeocodingResult, forecast: ForecastResponse) -> WeatherDetails {
+    let current = forecast.current;
+    let daily = forecast.daily;
+    let kelvin = |celsius: f32| celsius + 273.15;
+
+    WeatherDetails {
+        name: place.name,
+        coord: Coord {
+            lat: place.latitude,
+            lon: place.longitude,
+        },
+        dt: current.time,
+        weather: vec![WeatherCondition {
+            main: String::new(),
+            description: describe_weather_code(current.weather_code).to_string(),
+        }],
+        main: MainReadings {
+            temp: kelvin(current.temperature_2m),
+            temp_min: kelvin(
+                daily
+                    .temperature_2m_min
+                    .first()
+                    .copied()
+                    .unwrap_or(current.temperature_2m),
+            ),
+            temp_max: kelvin(
+                daily
+                    .temperature_2m_max
+                    .first()
+                    .copied()
+                    .unwrap_or(current.temperature_2m),
+            ),
+            humidity: current.relative_humidity_2m.round() as u8,
+            pressure: current.pressure_msl.round() as u16,
+        },
+        wind: WindInfo {
+            speed: current.wind_speed_10m,
+            deg: current.wind_direction_10m.round() as u16,
+        },
+        clouds: CloudCover {
+            all: current.cloud_cover.round() as u8,
+        },
+        sys: SysInfo {
+            country: place.country_code,
+            sunrise: daily.sunrise.first().copied().unwrap_or_default(),
+            sunset: daily.sunset.first().copied().unwrap_or_default(),
+        },
+        timezone: forecast.utc_offset_seconds,
+        stale: false,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HourlyForecastResponse {
+    pub utc_offset_seconds: i64,
+    pub hourly: HourlyReadings,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HourlyReadings {
+    pub time: Vec<u64>,
+    pub temperature_2m: Vec<f32>,
+    pub relative_humidity_2m: Vec<f32>,
+    pub pressure_msl: Vec<f32>,
+    pub wind_speed_10m: Vec<f32>,
+    pub wind_direction_10m: Vec<f32>,
+    pub cloud_cover: Vec<f32>,
+    pub weather_code: Vec<u8>,
+    pub precipitation_probability: Vec<Option<f32>>,
+    pub rain: Vec<f32>,
+    pub snowfall: Vec<f32>,
+}
+
+impl HourlyReadings {
+    // Every reading is indexed by the position of its hour, a short array would panic
+    // the fetch task halfway through
+    fn check_lengths(&self) -> Result<(), TermocastError> {
+        let hours = self.time.len();
+        let lengths = [
+            ("temperature_2m", self.temperature_2m.len()),
+            ("relative_humidity_2m", self.relative_humidity_2m.len()),
+            ("pressure_msl", self.pressure_msl.len()),
+            ("wind_speed_10m", self.wind_speed_10m.len()),
+            ("wind_direction_10m", self.wind_direction_10m.len()),
+            ("cloud_cover", self.cloud_cover.len()),
+            ("weather_code", self.weather_code.len()),
+            (
+                "precipitation_probability",
+                self.precipitation_probability.len(),
+            ),
+            ("rain", self.rain.len()),
+            ("snowfall", self.snowfall.len()),
+        ];
+        match lengths.iter().find(|(_, len)| *len != hours) {
+            Some((field, len)) => Err(TermocastError::Parse(serde::de::Error::custom(format!(
+                "hourly {} has {} values for {} hours",
+                field, len, hours
+            )))),
+            None => Ok(()),
+        }
+    }
+}
+
+// Fold hourly readings into 3 hour slots, dropping the ones that ended before `now`
+pub fn to_forecast(
+    name: String,
+    forecast: HourlyForecastResponse,
+    now: u64,
+) -> Result<Forecast, TermocastError> {
+    let hourly = forecast.hourly;
+    hourly.check_lengths()?;
+    let kelvin = |celsius: f32| celsius + 273.15;
+
+    let list = (0..hourly.time.len())
+        .step_by(SLOT_HOURS)
+        .filter(|&i| hourly.time[i] + (SLOT_HOURS as u64) * 3600 > now)
+        .map(|i| {
+            let slot = i..(i + SLOT_HOURS).min(hourly.time.len());
+            let temps = &hourly.temperature_2m[slot.clone()];
+            let rain: f32 = hourly.rain[slot.clone()].iter().sum();
+            // snowfall is reported in cm, the forecast model uses mm
+            let snow: f32 = hourly.snowfall[slot.clone()].iter().sum::<f32>() * 10.0;
+            let pop = hourly.precipitation_probability[slot]
+                .iter()
+                .flatten()
+                .fold(0.0_f32, |max, &p| max.max(p));
+
+            ForecastEntry {
+                dt: hourly.time[i],
+                main: MainReadings {
+                    temp: kelvin(hourly.temperature_2m[i]),
+                    temp_min: kelvin(temps.iter().copied().fold(f32::INFINITY, f32::min)),
+                    temp_max: kelvin(temps.iter().copied().fold(f32::NEG_INFINITY, f32::max)),
+                    humidity: hourly.relative_humidity_2m[i].round() as u8,
+                    pressure: hourly.pressure_msl[i].round() as u16,
+                },
+                weather: vec![WeatherCondition {
+                    main: String::new(),
+                    description: describe_weather_code(hourly.weather_code[i]).to_string(),
+                }],
+                wind: WindInfo {
+                    speed: hourly.wind_speed_10m[i],
+                    deg: hourly.wind_direction_10m[i].round() as u16,
+                },
+                clouds: CloudCover {
+                    all: hourly.cloud_cover[i].round() as u8,
+                },
+                pop: pop / 100.0,
+                rain: (rain > 0.0).then_some(Precipitation { three_hours: rain }),
+                snow: (snow > 0.0).then_some(Precipitation { three_hours: snow }),
+            }
+        })
+        .collect();
+
+    Ok(Forecast {
+        list,
+        city: ForecastCity {
+            name,
+            timezone: forecast.utc_offset_seconds,
+        },
+    })
+}
+
+// WMO weather interpretation codes, described with OpenWeatherMap's wording so the
+// same ascii art lookup works for both providers
+pub fn describe_weather_code(code: u8) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "scattered clouds",
+        3 => "overcast clouds",
+        45 => "fog",
+        48 => "depositing rime fog",
+        51 => "light intensity drizzle",
+        53 => "drizzle",
+        55 => "heavy intensity drizzle",
+        56 | 57 => "freezing drizzle",
+        61 => "light rain",
+        63 => "moderate rain",
+        65 => "heavy intensity rain",
+        66 | 67 => "freezing rain",
+        71 => "light snow",
+        73 => "snow",
+        75 => "heavy snow",
+        77 => "snow grains",
+        80 => "light intensity shower rain",
+        81 => "shower rain",
+        82 => "heavy intensity shower rain",
+        85 => "light shower snow",
+        86 => "heavy shower snow",
+        95 => "thunderstorm",
+        96 | 99 => "thunderstorm with hail",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::MockServer;
+    use crate::provider::client_with_timeouts;
+    use std::time::Duration;
+
+    const SEARCH: &str = include_str!("../../tests/fixtures/open_meteo/search.json");
+    const CURRENT: &str = include_str!("../../tests/fixtures/open_meteo/current.json");
+    const HOURLY: &str = include_str!("../../tests/fixtures/open_meteo/hourly.json");
+
+    // start of the first hour in `hourly.json`
+    const FIRST_HOUR: u64 = 1760767200;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    fn berlin() -> GeocodingResult {
+        let mut response: GeocodingResponse = serde_json::from_str(SEARCH).unwrap();
+        response.results.remove(0)
+    }
+
+    #[test]
+    fn current_readings_map_to_weather_details() {
+        let details = to_weather_details(berlin(), serde_json::from_str(CURRENT).unwrap());
+
+        assert_eq!(details.name, "Berlin");
+        assert_eq!(details.coord.lat, 52.52437);
+        assert_eq!(details.dt, 1760785200);
+        assert_eq!(details.weather[0].description, "overcast clouds");
+        // Open-Meteo reports Celsius, the model holds kelvin like OpenWeatherMap
+        assert_close(details.main.temp, 285.65);
+        assert_close(details.main.temp_min, 279.95);
+        assert_close(details.main.temp_max, 287.35);
+        assert_eq!(details.main.humidity, 72);
+        assert_eq!(details.main.pressure, 1018);
+        assert_close(details.wind.speed, 3.4);
+        assert_eq!(details.wind.deg, 248);
+        assert_eq!(details.clouds.all, 88);
+        assert_eq!(details.sys.country.as_deref(), Some("DE"));
+        assert_eq!(details.sys.sunrise, 1760764993);
+        assert_eq!(details.sys.sunset, 1760802788);
+        assert_eq!(details.timezone, 7200);
+    }
+
+    #[test]
+    fn hourly_readings_fold_into_three_hour_slots() {
+        let forecast = to_forecast(
+            "Berlin".to_string(),
+            serde_json::from_str(HOURLY).unwrap(),
+            FIRST_HOUR,
+        )
+        .unwrap();
+
+        assert_eq!(forecast.city.name, "Berlin");
+        assert_eq!(forecast.city.timezone, 7200);
+        let starts: Vec<u64> = forecast.list.iter().map(|entry| entry.dt).collect();
+        assert_eq!(
+            starts,
+            [FIRST_HOUR, FIRST_HOUR + 3 * 3600, FIRST_HOUR + 6 * 3600]
+        );
+
+        let slot = &forecast.list[1];
+        assert_close(slot.main.temp, 286.15);
+        assert_close(slot.main.temp_min, 286.15);
+        assert_close(slot.main.temp_max, 288.15);
+        assert_eq!(slot.main.humidity, 75);
+        assert_eq!(slot.main.pressure, 1018);
+        assert_eq!(slot.wind.deg, 195);
+        assert_eq!(slot.weather[0].description, "light rain");
+        assert_close(slot.rain.as_ref().unwrap().three_hours, 0.7);
+        assert!(slot.snow.is_none());
+    }
+
+    #[test]
+    fn slots_that_already_ended_are_dropped() {
+        // one second into the second slot, the first one is over
+        let now = FIRST_HOUR + 3 * 3600 + 1;
+        let forecast = to_forecast(
+            "Berlin".to_string(),
+            serde_json::from_str(HOURLY).unwrap(),
+            now,
+        )
+        .unwrap();
+
+        assert_eq!(forecast.list.len(), 2);
+        assert_eq!(forecast.list[0].dt, FIRST_HOUR + 3 * 3600);
+    }
+
+    #[test]
+    fn missing_precipitation_probability_is_skipped() {
+        let forecast = to_forecast(
+            "Berlin".to_string(),
+            serde_json::from_str(HOURLY).unwrap(),
+            FIRST_HOUR,
+        )
+        .unwrap();
+
+        // the highest known value of the slot, nulls do not count
+        assert_close(forecast.list[1].pop, 0.4);
+        // a slot with nothing but nulls has no precipitation chance at all
+        assert_close(forecast.list[2].pop, 0.0);
+        // snowfall comes in cm and is stored in mm
+        assert_close(forecast.list[2].snow.as_ref().unwrap().three_hours, 3.0);
+        assert!(forecast.list[2].rain.is_none());
+    }
+
+    #[test]
+    fn hourly_arrays_of_different_lengths_are_a_parse_error() {
+        let mut response: HourlyForecastResponse = serde_json::from_str(HOURLY).unwrap();
+        response.hourly.weather_code.pop();
+        let result = to_forecast("Berlin".to_string(), response, FIRST_HOUR);
+        let Err(TermocastError::Parse(e)) = result else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            e.to_string(),
+            "hourly weather_code has 8 values for 9 hours"
+        );
+    }
+
+    #[tokio::test]
+    async fn current_geocodes_the_name_first() {
+        let server = MockServer::start(vec![
+            ("/v1/search", 200, SEARCH.to_string()),
+            ("/v1/forecast", 200, CURRENT.to_string()),
+        ])
+        .await;
+        let provider = OpenMeteo::with_base_url(&server.url);
+
+        let details = provider
+            .current(&Location::Name("Berlin,de".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(details.name, "Berlin");
+        assert_close(details.main.temp, 285.65);
+
+        let requests = server.requests();
+        assert_eq!(requests[0], "/v1/search?name=Berlin&count=1&countryCode=DE");
+        assert!(requests[1].starts_with("/v1/forecast?latitude=52.52437&longitude=13.41053&"));
+    }
+
+    #[tokio::test]
+    async fn search_without_results_is_city_not_found() {
+        let server = MockServer::start(vec![(
+            "/v1/search",
+            200,
+            r#"{"generationtime_ms":0.2}"#.to_string(),
+        )])
+        .await;
+        let provider = OpenMeteo::with_base_url(&server.url);
+
+        let result = provider.geocode("Atlantis").await;
+        assert!(matches!(result, Err(TermocastError::CityNotFound(city)) if city == "Atlantis"));
+    }
+
+    #[tokio::test]
+    async fn a_stalled_connection_times_out() {
+        let server = MockServer::stalled().await;
+        let provider = OpenMeteo {
+            client: client_with_timeouts(Duration::from_millis(200), Duration::from_millis(200)),
+            ..OpenMeteo::with_base_url(&server.url)
+        };
+
+        let result = provider.geocode("Berlin").await;
+        assert!(
+            matches!(&result, Err(TermocastError::Network(e)) if e.is_timeout()),
+            "{:?}",
+            result
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use super::{GEOCODE_LIMIT, WeatherProvider, http_client};
+use crate::error::TermocastError;
+use crate::location::{Location, Place};
+use crate::types::{Forecast, WeatherDetails};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openweathermap.org";
+
+#[derive(Debug)]
+pub struct OpenWeatherMap {
+    pub api_key: Option<String>,
+    pub language: Option<String>, // `lang` parameter, translates the descriptions
+    pub base_url: String,         // scheme and host, without a trailing slash
+    pub client: reqwest::Client,
+}
+
+impl Default for OpenWeatherMap {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            language: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            client: http_client(),
+        }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenWeatherMap {
+    async fn current(&self, location: &Location) -> Result<WeatherDetails, TermocastError> {
+        let mut details: WeatherDetails = self.fetch_json("weather", location).await?;
+        // by coordinates OWM names the closest station, keep the name the place was picked by
+        if let Location::Coordinates {
+            name: Some(name), ..
+        } = location
+        {
+            details.name = name.clone();
+        }
+        Ok(details)
+    }
+
+    async fn forecast(&self, location: &Location) -> Result<Forecast, TermocastError> {
+        self.fetch_json("forecast", location).await
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Vec<Place>, TermocastError> {
+        let results: Vec<GeoResult> = self
+            .get_json(
+                &format!("{}/geo/1.0/direct", self.base_url),
+                &[
+                    ("q", query.to_string()),
+                    ("limit", GEOCODE_LIMIT.to_string()),
+                ],
+                query,
+            )
+            .await?;
+        if results.is_empty() {
+            return Err(TermocastError::CityNotFound(query.to_string()));
+        }
+        Ok(results
+            .into_iter()
+            .map(|result| Place {
+                name: result.name,
+                state: result.state,
+                country: result.country,
+                lat: result.lat,
+                lon: result.lon,
+            })
+            .collect())
+    }
+}
+
+// One entry of the `/geo/1.0/direct` response
+#[derive(Debug, Deserialize)]
+struct GeoResult {
+    name: String,
+    lat: f64,
+    lon: f64,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+}
+
+// Body OpenWeatherMap sends along with an error status, e.g. `{"cod":"401","message":"Invalid API key..."}`
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+impl OpenWeatherMap {
+    async fn fetch_json<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        location: &Location,
+    ) -> Result<T, TermocastError> {
+        let mut query = match location {
+            Location::Name(city) => vec![("q", city.clone())],
+            Location::Coordinates { lat, lon, .. } => {
+                vec![("lat", lat.to_string()), ("lon", lon.to_string())]
+            }
+            Location::Zip {
+                code,
+                country: Some(country),
+            } => vec![("zip", format!("{},{}", code, country))],
+            Location::Zip { code, .. } => vec![("zip", code.clone())],
+            Location::Id(id) => vec![("id", id.to_string())],
+        };
+        if let Some(language) = &self.language {
+            query.push(("lang", language.clone()));
+        }
+        let url = format!("{}/data/2.5/{}", self.base_url, endpoint);
+        self.get_json(&url, &query, &location.to_string()).await
+    }
+
+    // Send a GET with the API key added to `query`. Parameters go through reqwest's query
+    // builder so names like "São Paulo" or "A & B" arrive encoded as they should.
+    // `place` names what was asked for when OpenWeatherMap answers 404.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        place: &str,
+    ) -> Result<T, TermocastError> {
+        let key = self
+            .api_key
+            .as_deref()
+            .ok_or(TermocastError::MissingApiKey)?;
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .query(&[("appid", key)])
+            .send()
+            .await?;
+        let status = response.status();
+        let weather_text = response.text().await?;
+
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(TermocastError::CityNotFound(place.to_string()));
+        }
+        if !status.is_success() {
+            let message = serde_json::from_str::<ErrorBody>(&weather_text)
+                .map_or(weather_text, |body| body.message);
+            return Err(TermocastError::Http { status, message });
+        }
+        Ok(serde_json::from_str(&weather_text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::MockServer;
+    use crate::provider::client_with_timeouts;
+    use std::time::Duration;
+
+    const WEATHER: &str = include_str!("../../tests/fixtures/openweathermap/weather.json");
+    const GEOCODE: &str = include_str!("../../tests/fixtures/openweathermap/geocode.json");
+
+    fn provider(server: &MockServer) -> OpenWeatherMap {
+        OpenWeatherMap {
+            api_key: Some("key".to_string()),
+            language: None,
+            base_url: server.url.clone(),
+            client: http_client(),
+        }
+    }
+
+    #[tokio::test]
+    async fn current_maps_the_response() {
+        let server = MockServer::start(vec![("/data/2.5/weather", 200, WEATHER.to_string())]).await;
+        let provider: &dyn WeatherProvider = &provider(&server);
+
+        let details = provider
+            .current(&Location::Name("London".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(details.name, "London");
+        assert_eq!(details.main.temp, 286.48);
+        assert_eq!(details.main.humidity, 82);
+        assert_eq!(details.weather[0].main, "Rain");
+        assert_eq!(details.weather[0].description, "light rain");
+        assert_eq!(details.wind.deg, 230);
+        assert_eq!(details.sys.country.as_deref(), Some("GB"));
+        assert_eq!(details.timezone, 3600);
+        assert!(!details.stale);
+        assert_eq!(server.requests(), ["/data/2.5/weather?q=London&appid=key"]);
+    }
+
+    #[tokio::test]
+    async fn coordinates_keep_the_picked_name() {
+        let server = MockServer::start(vec![("/data/2.5/weather", 200, WEATHER.to_string())]).await;
+        let location = Location::Coordinates {
+            lat: 51.5,
+            lon: -0.12,
+            name: Some("City of London".to_string()),
+        };
+
+        let details = provider(&server).current(&location).await.unwrap();
+        assert_eq!(details.name, "City of London");
+        assert_eq!(
+            server.requests(),
+            ["/data/2.5/weather?lat=51.5&lon=-0.12&appid=key"]
+        );
+    }
+
+    #[tokio::test]
+    async fn not_found_is_city_not_found() {
+        let body = r#"{"cod":"404","message":"city not found"}"#.to_string();
+        let server = MockServer::start(vec![("/data/2.5/weather", 404, body)]).await;
+
+        let result = provider(&server)
+            .current(&Location::Name("Nowhere".to_string()))
+            .await;
+        assert!(matches!(result, Err(TermocastError::CityNotFound(city)) if city == "Nowhere"));
+    }
+
+    #[tokio::test]
+    async fn error_status_carries_the_message() {
+        let body = r#"{"cod":401,"message":"Invalid API key."}"#.to_string();
+        let server = MockServer::start(vec![("/data/2.5/weather", 401, body)]).await;
+
+        let result = provider(&server)
+            .current(&Location::Name("London".to_string()))
+            .await;
+        match result {
+            Err(TermocastError::Http { status, message }) => {
+                assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+                assert_eq!(message, "Invalid API key.");
+            }
+            other => panic!("expected an HTTP error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_key_sends_nothing() {
+        let server = MockServer::start(vec![("/data/2.5/weather", 200, WEATHER.to_string())]).await;
+        let provider = OpenWeatherMap {
+            api_key: None,
+            ..provider(&server)
+        };
+
+        let result = provider
+            .current(&Location::Name("London".to_string()))
+            .await;
+        assert!(matches!(result, Err(TermocastError::MissingApiKey)));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn geocode_lists_the_places() {
+        let server = MockServer::start(vec![("/geo/1.0/direct", 200, GEOCODE.to_string())]).await;
+
+        let places = provider(&server).geocode("London").await.unwrap();
+        let labels: Vec<String> = places.iter().map(Place::label).collect();
+        assert_eq!(labels, ["London, England, GB", "London, Ontario, CA"]);
+        assert_eq!(places[1].lat, 42.9832406);
+        assert_eq!(
+            server.requests(),
+            ["/geo/1.0/direct?q=London&limit=5&appid=key"]
+        );
+    }
+
+    // The request target the server saw for a search by name
+    async fn query_for(city: &str, api_key: &str) -> String {
+        let server = MockServer::start(vec![("/data/2.5/weather", 200, WEATHER.to_string())]).await;
+        let provider = OpenWeatherMap {
+            api_key: Some(api_key.to_string()),
+            ..provider(&server)
+        };
+        provider
+            .current(&Location::Name(city.to_string()))
+            .await
+            .unwrap();
+        server.requests().remove(0)
+    }
+
+    #[tokio::test]
+    async fn non_ascii_names_are_percent_encoded() {
+        assert_eq!(
+            query_for("São Paulo", "key").await,
+            "/data/2.5/weather?q=S%C3%A3o+Paulo&appid=key"
+        );
+    }
+
+    #[tokio::test]
+    async fn spaces_are_encoded() {
+        assert_eq!(
+            query_for("Ho Chi Minh City", "key").await,
+            "/data/2.5/weather?q=Ho+Chi+Minh+City&appid=key"
+        );
+    }
+
+    #[tokio::test]
+    async fn ampersand_and_hash_stay_inside_the_name() {
+        assert_eq!(
+            query_for("Sint & Co #2", "key").await,
+            "/data/2.5/weather?q=Sint+%26+Co+%232&appid=key"
+        );
+    }
+
+    #[tokio::test]
+    async fn api_key_is_encoded_too() {
+        assert_eq!(
+            query_for("London", "k&y#1=").await,
+            "/data/2.5/weather?q=London&appid=k%26y%231%3D"
+        );
+    }
+
+    #[tokio::test]
+    async fn geocode_without_matches_is_city_not_found() {
+        let server = MockServer::start(vec![("/geo/1.0/direct", 200, "[]".to_string())]).await;
+
+        let result = provider(&server).geocode("Atlantis").await;
+        assert!(matches!(result, Err(TermocastError::CityNotFound(city)) if city == "Atlantis"));
+    }
+
+    #[tokio::test]
+    async fn a_stalled_connection_times_out() {
+        let server = MockServer::stalled().await;
+        let provider = OpenWeatherMap {
+            client: client_with_timeouts(Duration::from_millis(200), Duration::from_millis(200)),
+            ..provider(&server)
+        };
+
+        let result = provider
+            .current(&Location::Name("London".to_string()))
+            .await;
+        match result {
+            Err(e @ TermocastError::Network(_)) => assert!(e.is_unreachable()),
+            other => panic!("expected a network error, got {:?}", other),
+        }
+    }
+}
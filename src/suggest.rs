@@ -0,0 +1,39 @@
+use crate::cities;
+
+// How many suggestions the dropdown shows at most
+pub const MAX_SUGGESTIONS: usize = 6;
+
+// Completions for what has been typed so far. Earlier searches come first, newest
+// before older, then cities from the bundled database as `Name,CC` in order of size.
+// History entries have to start with the typed text, ignoring case, while cities are
+// also found without accents or with a small typo. Nothing is suggested for text that
+// is already complete.
+pub fn suggestions(typed: &str, history: &[String]) -> Vec<String> {
+    let typed = typed.trim_start();
+    if typed.is_empty() {
+        return Vec::new();
+    }
+    let lower = typed.to_lowercase();
+
+    let from_history = history
+        .iter()
+        .rev()
+        .filter(|entry| entry.to_lowercase().starts_with(&lower))
+        .cloned();
+    let from_cities = cities::search(typed, MAX_SUGGESTIONS)
+        .into_iter()
+        .map(|city| city.query());
+
+    let mut found: Vec<String> = Vec::new();
+    for candidate in from_history.chain(from_cities) {
+        if !candidate.eq_ignore_ascii_case(typed)
+            && !found.iter().any(|f| f.eq_ignore_ascii_case(&candidate))
+        {
+            found.push(candidate);
+            if found.len() == MAX_SUGGESTIONS {
+                break;
+            }
+        }
+    }
+    found
+}
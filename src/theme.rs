@@ -0,0 +1,24 @@
+use ratatui::style::Color;
+use serde::Deserialize;
+
+// Colors of the interface, the `[theme]` table of the config file. Values are color
+// names like "cyan" or hex codes like "#ff8800".
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub accent: Color, // borders and titles
+    pub temperature: Color,
+    pub precipitation: Color,
+    pub wind: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            accent: Color::Reset,
+            temperature: Color::Yellow,
+            precipitation: Color::Blue,
+            wind: Color::Cyan,
+        }
+    }
+}
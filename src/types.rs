@@ -1,8 +1,9 @@
 #![allow(unused)]
-use serde::Deserialize;
-#[derive(Debug, Deserialize)]
+use serde::{Deserialize, Serialize};
+#[derive(Debug, Serialize, Deserialize)]
 pub struct WeatherDetails {
     pub name: String,
+    pub coord: Coord,
     pub dt: u64, // dt is the timestamp of the data calculation
     pub weather: Vec<WeatherCondition>,
     pub main: MainReadings,
@@ -10,20 +11,33 @@ pub struct WeatherDetails {
     pub clouds: CloudCover,
     pub sys: SysInfo,
     pub timezone: i64, // it works as an offset in seconds from UTC
+    // served from the cache because the service could not be reached
+    #[serde(skip)]
+    pub stale: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Coord {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SysInfo {
+    #[serde(default)]
+    pub country: Option<String>, // ISO 3166 country code
     pub sunrise: u64,
     pub sunset: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct WeatherCondition {
-    pub description: String,
+    #[serde(default)]
+    pub main: String, // group like "Rain" or "Clouds", always in English
+    pub description: String, // follows the requested language
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MainReadings {
     pub temp: f32,
     pub temp_min: f32,
@@ -32,13 +46,45 @@ pub struct MainReadings {
     pub pressure: u16,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct WindInfo {
     pub speed: f32,
     pub deg: u16,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CloudCover {
     pub all: u8,
 }
+
+// Response of the 5 day / 3 hour `/data/2.5/forecast` endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Forecast {
+    pub list: Vec<ForecastEntry>,
+    pub city: ForecastCity,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForecastCity {
+    pub name: String,
+    pub timezone: i64, // offset in seconds from UTC, same as `WeatherDetails::timezone`
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForecastEntry {
+    pub dt: u64, // start of the 3 hour slot
+    pub main: MainReadings,
+    pub weather: Vec<WeatherCondition>,
+    pub wind: WindInfo,
+    pub clouds: CloudCover,
+    #[serde(default)]
+    pub pop: f32, // probability of precipitation, 0.0 to 1.0
+    pub rain: Option<Precipitation>,
+    pub snow: Option<Precipitation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Precipitation {
+    #[serde(rename = "3h", default)]
+    pub three_hours: f32, // volume in mm over the 3 hour slot
+}
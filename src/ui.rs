@@ -1,194 +1,917 @@
 use crate::art::AsciiArt;
-use crate::fetch_weather;
-use crate::types::WeatherDetails;
+use crate::chart::HourlyChart;
+use crate::cities;
+use crate::compare::{CompareRow, CompareTable, Sort, SortColumn};
+use crate::config::Settings;
+use crate::error::TermocastError;
+use crate::favorites::{Favorite, Favorites};
+use crate::forecast::ForecastPanel;
+use crate::format::{format_age, format_reading};
+use crate::history::History;
+use crate::input::TextInput;
+use crate::keymap::{Action, KeyBinding, Keymap, Mode};
+use crate::location::{Location, Place};
+use crate::provider::WeatherProvider;
+use crate::suggest::suggestions;
+use crate::theme::Theme;
+use crate::types::{Forecast, WeatherDetails};
+use crate::units::Units;
 use crossterm::cursor;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, poll};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind};
 use crossterm::execute;
+use futures::StreamExt;
 use ratatui::{
     DefaultTerminal, Frame,
     buffer::Buffer,
-    layout::{Position, Rect},
-    style::Stylize,
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Style, Stylize},
     symbols::border,
-    text::Line,
-    widgets::{Block, Paragraph, Widget},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListState, Paragraph, StatefulWidget, Widget, Wrap},
 };
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
-use std::{io, time::Duration};
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, interval, interval_at};
+use unicode_width::UnicodeWidthStr;
 
-#[derive(Default, Debug)]
+// borders, weekday, icon, condition and temperatures
+const FORECAST_HEIGHT: u16 = 10;
+const SIDEBAR_WIDTH: u16 = 28;
+const INPUT_WIDTH: u16 = 20;
+// the "updated Xm ago" label is the only thing that changes without an event
+const AGE_REDRAW_INTERVAL: Duration = Duration::from_secs(60);
+
+// Everything the event loop reacts to besides terminal input. Background tasks never
+// touch the app state, they send their results here and the loop applies them.
+#[derive(Debug)]
+enum AppEvent {
+    Fetched {
+        generation: u64,
+        outcome: FetchOutcome,
+    },
+    Compared {
+        round: u64,
+        index: usize,
+        result: Result<WeatherDetails, TermocastError>,
+    },
+}
+
+#[derive(Debug)]
 struct App {
-    city: String,
-    weather_details: Arc<Mutex<Option<WeatherDetails>>>,
+    input: TextInput,
+    history: History,
+    // Ctrl+R reverse search through the history, active while set
+    history_search: Option<HistorySearch>,
+    // completions for the typed text, shown above the input while there are any
+    suggestions: Vec<String>,
+    selected_suggestion: usize,
+    weather_details: Option<WeatherDetails>,
+    forecast: Option<Forecast>,
+    error: Option<TermocastError>,
+    // places matching an ambiguous name, the picker is open while this is set
+    candidates: Option<Vec<Place>>,
+    selected_candidate: usize,
+    // the place shown, already geocoded so refreshing it never asks for a pick again
+    last_location: Option<Location>,
+    refresh_interval: Duration,
+    provider: Arc<dyn WeatherProvider>,
+    // only the cache is read, names are resolved with the bundled cities alone
+    offline: bool,
     art: AsciiArt,
+    units: Units,
+    theme: Theme,
+    keymap: Keymap,
+    mode: Mode,
+    show_help: bool,
+    favorites: Favorites,
+    selected_favorite: Option<usize>,
+    favorites_error: Option<String>,
+    comparing: bool,
+    compare_rows: Vec<CompareRow>,
+    // bumped every time the comparison is fetched, rows from older rounds are dropped
+    compare_round: u64,
+    compare_sort: Sort,
     exit: bool,
-    isfetching: Arc<AtomicBool>,
-    fetched_once: bool,
-    cursor_position: usize,
+    isfetching: bool,
+    // bumped for every fetch, results of anything but the latest are dropped
+    generation: u64,
+    fetch_task: Option<JoinHandle<()>>,
+    events: UnboundedSender<AppEvent>,
 }
 
 impl App {
-    pub fn run(
+    // Build the app from the resolved settings. When a default location is configured its
+    // weather is requested right away so the first screen already shows something useful.
+    fn new(
+        provider: Arc<dyn WeatherProvider>,
+        settings: Settings,
+        favorites: Favorites,
+        history: History,
+        events: UnboundedSender<AppEvent>,
+    ) -> Self {
+        let mut app = Self {
+            input: TextInput::new(usize::from(INPUT_WIDTH - 2)),
+            history,
+            history_search: None,
+            suggestions: Vec::new(),
+            selected_suggestion: 0,
+            weather_details: None,
+            forecast: None,
+            error: None,
+            candidates: None,
+            selected_candidate: 0,
+            last_location: None,
+            refresh_interval: settings.refresh_interval,
+            provider,
+            offline: settings.offline,
+            art: AsciiArt::default(),
+            units: settings.units,
+            theme: settings.theme,
+            keymap: settings.keybindings,
+            mode: Mode::Typing,
+            show_help: false,
+            favorites,
+            selected_favorite: None,
+            favorites_error: None,
+            comparing: false,
+            compare_rows: Vec::new(),
+            compare_round: 0,
+            compare_sort: Sort::default(),
+            exit: false,
+            isfetching: false,
+            generation: 0,
+            fetch_task: None,
+            events,
+        };
+        if let Some(location) = settings.default_city {
+            app.handle_weather_fetch(location);
+        }
+        app
+    }
+
+    // Fetch the shown location again, unless a fetch or a pick is still pending. This
+    // goes to the service even when the cache still has a fresh copy.
+    fn refresh(&mut self) {
+        if self.isfetching || self.candidates.is_some() {
+            return;
+        }
+        if let Some(location) = self.last_location.clone() {
+            self.start_fetch(location, true);
+        }
+    }
+
+    // Wait for terminal input, results of background tasks and timers, and redraw only
+    // after something happened instead of spinning on a poll
+    pub async fn run(
         &mut self,
         terminal: &mut DefaultTerminal,
+        mut app_events: UnboundedReceiver<AppEvent>,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut input = EventStream::new();
+        // the first refresh is one interval away, the data was only just fetched
+        let mut refresh = interval_at(
+            Instant::now() + self.refresh_interval,
+            self.refresh_interval,
+        );
+        let mut age_redraw = interval(AGE_REDRAW_INTERVAL);
+        let mut dirty = true;
+
         while !self.exit {
-            terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
+            if dirty {
+                terminal.draw(|frame| self.draw(frame))?;
+                dirty = false;
+            }
+            tokio::select! {
+                event = input.next() => match event {
+                    Some(event) => {
+                        self.handle_event(event?);
+                        dirty = true;
+                    }
+                    None => break,
+                },
+                Some(event) = app_events.recv() => {
+                    self.handle_app_event(event);
+                    dirty = true;
+                }
+                _ = refresh.tick() => self.refresh(),
+                _ = age_redraw.tick() => dirty = true,
+            }
         }
         Ok(())
     }
-    fn enter_character(&mut self, c: char) {
-        let index = self.byte_index();
-        self.city.insert(index, c);
-        self.move_cursor_right();
-    }
-
-    // this function will convert the cursor position in terms of characters to byte index in the string
-    fn byte_index(&self) -> usize {
-        self.city
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.cursor_position)
-            .unwrap_or(self.city.len())
-    }
 
     fn draw(&self, frame: &mut Frame) {
         frame.render_widget(self, frame.area());
+        // no cursor while keys are not going to the input
+        if self.mode == Mode::Navigation {
+            return;
+        }
+        // inside the border of the input box
+        let column = match &self.history_search {
+            Some(search) => search.query.cursor_column(),
+            None => self.input.cursor_column(),
+        };
         frame.set_cursor_position(Position::new(
-            self.cursor_position as u16 + 3,
+            frame.area().x + 3 + column,
             frame.area().y + frame.area().height - 2,
         ));
     }
 
-    fn reset_cursor_position(&mut self) {
-        self.cursor_position = 0;
+    fn handle_event(&mut self, event: Event) {
+        if self.candidates.is_some() {
+            self.handle_picker_event(event);
+            return;
+        }
+        if self.history_search.is_some() {
+            self.handle_history_search_event(event);
+            return;
+        }
+        let Event::Key(
+            key @ KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            },
+        ) = event
+        else {
+            return;
+        };
+        if self.show_help {
+            self.show_help = false;
+            return;
+        }
+        // the comparison has keys of its own in navigation mode, plain digits sort it there
+        let action = match self.mode {
+            Mode::Navigation if self.comparing => self.keymap.comparison_action(&key),
+            mode => self.keymap.action(mode, &key),
+        };
+        if let Some(action) = action {
+            self.perform(action, &key);
+            return;
+        }
+        // everything else is text editing
+        if self.mode == Mode::Typing && self.input.handle_key(&key) {
+            self.history.reset_browsing();
+            self.update_suggestions();
+        }
     }
 
-    fn move_cursor_left(&mut self) {
-        self.cursor_position = self.cursor_position.saturating_sub(1);
+    fn perform(&mut self, action: Action, key: &KeyEvent) {
+        match action {
+            Action::Quit => self.exit(),
+            Action::Help => self.show_help = true,
+            Action::Navigate => {
+                self.mode = Mode::Navigation;
+                self.suggestions.clear();
+            }
+            Action::Type => self.mode = Mode::Typing,
+            Action::Search => {
+                let query = self.input.value().to_string();
+                self.submit(&query);
+            }
+            // while suggestions are shown the history keys move through them instead
+            Action::AcceptSuggestion => {
+                if let Some(suggestion) = self.suggestions.get(self.selected_suggestion) {
+                    self.input.set(&suggestion.clone());
+                    self.suggestions.clear();
+                }
+            }
+            Action::HistoryOlder if !self.suggestions.is_empty() => {
+                self.selected_suggestion = self.selected_suggestion.saturating_sub(1);
+            }
+            Action::HistoryOlder => {
+                if let Some(entry) = self.history.older(self.input.value()) {
+                    self.input.set(entry);
+                }
+            }
+            Action::HistoryNewer if !self.suggestions.is_empty() => {
+                self.selected_suggestion =
+                    (self.selected_suggestion + 1).min(self.suggestions.len() - 1);
+            }
+            Action::HistoryNewer => {
+                if let Some(entry) = self.history.newer() {
+                    self.input.set(entry);
+                }
+            }
+            Action::HistorySearch => {
+                self.history_search = Some(HistorySearch {
+                    query: TextInput::new(usize::from(INPUT_WIDTH - 2)),
+                    matched: None,
+                });
+            }
+            Action::CycleUnits => self.units = self.units.next_system(),
+            Action::SaveFavorite => self.save_favorite(),
+            Action::RemoveFavorite => self.remove_favorite(),
+            Action::PreviousFavorite => {
+                let index = self.selected_favorite.map_or(0, |i| i.saturating_sub(1));
+                self.select_favorite(index);
+            }
+            Action::NextFavorite => {
+                let index = self.selected_favorite.map_or(0, |i| i + 1);
+                self.select_favorite(index);
+            }
+            // the digit of the key is the number of the favorite
+            Action::JumpFavorite => {
+                if let KeyCode::Char(c @ '1'..='9') = key.code {
+                    self.select_favorite(c as usize - '1' as usize);
+                }
+            }
+            Action::Compare => self.toggle_compare(),
+            // the digit of the key is the number of the column, digits past the last
+            // column do nothing and any other key moves on to the next column
+            Action::SortColumn if self.comparing => {
+                let column = match key.code {
+                    KeyCode::Char(c @ '1'..='9') => {
+                        SortColumn::ALL.get(c as usize - '1' as usize).copied()
+                    }
+                    _ => Some(self.compare_sort.column.next()),
+                };
+                if let Some(column) = column {
+                    self.compare_sort = self.compare_sort.select(column);
+                }
+            }
+            Action::SortColumn => {}
+            Action::Refresh if self.comparing => self.handle_compare_fetch(true),
+            Action::Refresh => self.refresh(),
+        }
     }
 
-    fn move_cursor_right(&mut self) {
-        if self.cursor_position < self.city.len() {
-            self.cursor_position = self.cursor_position.saturating_add(1);
+    // Search for what was typed and remember it in the history
+    fn submit(&mut self, query: &str) {
+        if query.trim().is_empty() {
+            return;
         }
-    }
-    fn delete_character(&mut self) {
-        let current_index = self.cursor_position;
-        if current_index != 0 {
-            let from_left_to_current_index = current_index - 1;
-            let before_character_to_delete = self.city.chars().take(from_left_to_current_index);
-            let after_character_to_delete = self.city.chars().skip(current_index);
-            self.city = before_character_to_delete
-                .chain(after_character_to_delete)
-                .collect();
-            self.move_cursor_left();
+        match query.parse() {
+            Ok(location) => {
+                self.history.add(query);
+                // losing the history is not worth interrupting the search for
+                let _ = self.history.save();
+                self.handle_weather_fetch(location);
+            }
+            Err(e) => self.show_error(TermocastError::InvalidLocation(e)),
         }
+        self.input.clear();
+        self.suggestions.clear();
+    }
+
+    fn update_suggestions(&mut self) {
+        self.suggestions = suggestions(self.input.value(), self.history.entries());
+        self.selected_suggestion = 0;
     }
 
-    fn handle_events(&mut self) -> io::Result<()> {
-        if poll(Duration::from_micros(1))? {
-            match event::read()? {
-                Event::Key(KeyEvent {
-                    code: KeyCode::Esc,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    self.exit();
+    // Like Ctrl+R in a shell: typing narrows the match, Ctrl+R again goes to an older
+    // one, Enter searches for the match, Esc gives up and any other key keeps the match
+    // in the input for editing. The keys are the typing keys of those actions.
+    fn handle_history_search_event(&mut self, event: Event) {
+        let Event::Key(
+            key @ KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            },
+        ) = event
+        else {
+            return;
+        };
+        let Some(search) = self.history_search.as_mut() else {
+            return;
+        };
+        let matched = search
+            .matched
+            .map(|index| self.history.entries()[index].clone());
+
+        match self.keymap.action(Mode::Typing, &key) {
+            Some(Action::HistorySearch) => {
+                let before = search.matched.unwrap_or(self.history.entries().len());
+                if let Some(index) = self.history.search(search.query.value(), before) {
+                    search.matched = Some(index);
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Right,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    self.move_cursor_right();
+            }
+            Some(Action::Navigate) => self.history_search = None,
+            Some(Action::Search) => {
+                self.history_search = None;
+                if let Some(entry) = matched {
+                    self.submit(&entry);
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Left,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    self.move_cursor_left();
+            }
+            None if matches!(
+                key.code,
+                KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete
+            ) && search.query.handle_key(&key) =>
+            {
+                search.matched = self
+                    .history
+                    .search(search.query.value(), self.history.entries().len());
+            }
+            _ => {
+                self.history_search = None;
+                if let Some(entry) = matched {
+                    self.input.set(&entry);
+                    self.suggestions.clear();
                 }
+            }
+        }
+    }
 
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('c'),
-                    modifiers: KeyModifiers::CONTROL,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    self.exit();
+    // While the picker is open the keys only move through the candidates, with the typing
+    // keys of the history, search and navigate actions
+    fn handle_picker_event(&mut self, event: Event) {
+        let Event::Key(
+            key @ KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            },
+        ) = event
+        else {
+            return;
+        };
+        let count = self.candidates.as_ref().map_or(0, Vec::len);
+        match self.keymap.action(Mode::Typing, &key) {
+            Some(Action::HistoryOlder) => {
+                self.selected_candidate = self.selected_candidate.saturating_sub(1)
+            }
+            Some(Action::HistoryNewer) => {
+                self.selected_candidate = (self.selected_candidate + 1).min(count.saturating_sub(1))
+            }
+            Some(Action::Search) => {
+                let place = self.candidates.take().and_then(|mut places| {
+                    (self.selected_candidate < places.len())
+                        .then(|| places.swap_remove(self.selected_candidate))
+                });
+                if let Some(place) = place {
+                    self.handle_weather_fetch(place.location());
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('q'),
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    self.exit();
+            }
+            Some(Action::Navigate) => self.candidates = None,
+            _ => {}
+        }
+    }
+
+    // Select a favorite and show its weather, out of range indexes are ignored
+    fn select_favorite(&mut self, index: usize) {
+        let Some(favorite) = self.favorites.entries.get(index) else {
+            return;
+        };
+        let location = favorite.location();
+        self.selected_favorite = Some(index);
+        self.handle_weather_fetch(location);
+    }
+
+    fn save_favorite(&mut self) {
+        let Some(favorite) = self.weather_details.as_ref().map(Favorite::from_details) else {
+            return;
+        };
+        self.selected_favorite = Some(self.favorites.add(favorite));
+        self.persist_favorites();
+    }
+
+    fn remove_favorite(&mut self) {
+        let Some(index) = self.selected_favorite else {
+            return;
+        };
+        self.favorites.remove(index);
+        self.selected_favorite = match self.favorites.len() {
+            0 => None,
+            len => Some(index.min(len - 1)),
+        };
+        self.persist_favorites();
+    }
+
+    fn persist_favorites(&mut self) {
+        self.favorites_error = self
+            .favorites
+            .save()
+            .err()
+            .map(|e| format!("Could not save favorites: {}", e));
+    }
+
+    fn toggle_compare(&mut self) {
+        self.comparing = !self.comparing;
+        if self.comparing {
+            self.handle_compare_fetch(false);
+        }
+    }
+
+    // Fetch every favorite at once, one task per city. Each task reports its own row as
+    // soon as it is done so fast cities show up without waiting for the slow ones.
+    fn handle_compare_fetch(&mut self, refresh: bool) {
+        self.compare_round += 1;
+        self.compare_rows = self
+            .favorites
+            .entries
+            .iter()
+            .map(|favorite| CompareRow {
+                label: favorite.label(),
+                result: None,
+            })
+            .collect();
+
+        for (index, favorite) in self.favorites.entries.iter().enumerate() {
+            let provider = Arc::clone(&self.provider);
+            let events = self.events.clone();
+            let location = favorite.location();
+            let round = self.compare_round;
+            tokio::spawn(async move {
+                let result = if refresh {
+                    provider.refresh_current(&location).await
+                } else {
+                    provider.current(&location).await
+                };
+                // the loop is gone once the app exits, nobody is left to tell
+                let _ = events.send(AppEvent::Compared {
+                    round,
+                    index,
+                    result,
+                });
+            });
+        }
+    }
+
+    fn exit(&mut self) {
+        self.exit = true;
+    }
+
+    fn handle_weather_fetch(&mut self, location: Location) {
+        self.start_fetch(location, false);
+    }
+
+    // Start fetching a location, with `refresh` past fresh copies in the cache. A search
+    // started while another one is still running supersedes it: the older task is
+    // aborted, and should its result already be on the way, the generation check drops it.
+    fn start_fetch(&mut self, location: Location, refresh: bool) {
+        self.generation += 1;
+        if let Some(task) = self.fetch_task.take() {
+            task.abort();
+        }
+        self.isfetching = true;
+        self.selected_candidate = 0;
+
+        let provider = Arc::clone(&self.provider);
+        let events = self.events.clone();
+        let generation = self.generation;
+        let offline = self.offline;
+        self.fetch_task = Some(tokio::spawn(async move {
+            let outcome = fetch_weather(provider.as_ref(), location, offline, refresh).await;
+            let _ = events.send(AppEvent::Fetched {
+                generation,
+                outcome,
+            });
+        }));
+    }
+
+    fn handle_app_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::Fetched {
+                generation,
+                outcome,
+            } => {
+                if generation != self.generation {
+                    return;
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Enter,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    if self.city.is_empty() {
-                        return Ok(());
+                self.isfetching = false;
+                match outcome {
+                    FetchOutcome::Ambiguous(places) => self.candidates = Some(places),
+                    FetchOutcome::Fetched {
+                        location,
+                        details,
+                        forecast,
+                    } => {
+                        self.last_location = Some(location);
+                        // a failed forecast only hides the forecast panels, the error shown
+                        // is the one for the current conditions
+                        self.forecast = forecast;
+                        (self.weather_details, self.error) = match *details {
+                            Ok(details) => (Some(details), None),
+                            Err(e) => (None, Some(e)),
+                        };
                     }
-                    self.handle_weather_fetch();
-                    self.city.clear();
-                    self.reset_cursor_position();
-                    self.fetched_once = true;
-                    self.isfetching.store(true, Ordering::SeqCst);
+                    FetchOutcome::Failed(e) => self.show_error(e),
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char(c),
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    self.enter_character(c);
+            }
+            AppEvent::Compared {
+                round,
+                index,
+                result,
+            } => {
+                if round == self.compare_round
+                    && let Some(row) = self.compare_rows.get_mut(index)
+                {
+                    row.result = Some(result);
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Backspace,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) => {
-                    self.delete_character();
+            }
+        }
+    }
+
+    fn show_error(&mut self, error: TermocastError) {
+        self.weather_details = None;
+        self.forecast = None;
+        self.error = Some(error);
+    }
+}
+
+#[derive(Debug)]
+struct HistorySearch {
+    query: TextInput,
+    matched: Option<usize>, // index into the history entries
+}
+
+#[derive(Debug)]
+enum FetchOutcome {
+    // several places match the typed name, the user has to pick one
+    Ambiguous(Vec<Place>),
+    Fetched {
+        location: Location,
+        details: Box<Result<WeatherDetails, TermocastError>>,
+        forecast: Option<Forecast>,
+    },
+    Failed(TermocastError),
+}
+
+// Typed names are geocoded first. With several matches the user picks one and the weather
+// is fetched by the coordinates of that place.
+async fn fetch_weather(
+    provider: &dyn WeatherProvider,
+    location: Location,
+    offline: bool,
+    refresh: bool,
+) -> FetchOutcome {
+    let location = match location {
+        Location::Name(query) => {
+            // the bundled database settles names given with their country, and offline
+            // it is all there is. Otherwise its cities join the geocoder's in the picker,
+            // "London" may as well mean the one in Ontario.
+            let local: Vec<Place> = cities::lookup(&query).iter().map(|c| c.place()).collect();
+            let mut places = if !local.is_empty() && (offline || cities::names_country(&query)) {
+                local
+            } else {
+                match provider.geocode(&query).await {
+                    Ok(found) => merge_places(local, found),
+                    Err(_) if !local.is_empty() => local,
+                    Err(e) => return FetchOutcome::Failed(e),
                 }
-                _ => {}
+            };
+            if places.len() != 1 {
+                return FetchOutcome::Ambiguous(places);
             }
+            places.remove(0).location()
+        }
+        location => location,
+    };
+    let (details, forecast) = if refresh {
+        tokio::join!(
+            provider.refresh_current(&location),
+            provider.refresh_forecast(&location)
+        )
+    } else {
+        tokio::join!(provider.current(&location), provider.forecast(&location))
+    };
+    FetchOutcome::Fetched {
+        location,
+        details: Box::new(details),
+        forecast: forecast.ok(),
+    }
+}
+
+// Bundled cities first, then what the geocoder found elsewhere. Its copy of a bundled
+// city sits a few hundred meters off at most, those are dropped.
+fn merge_places(mut local: Vec<Place>, found: Vec<Place>) -> Vec<Place> {
+    let known: Vec<(f64, f64)> = local.iter().map(|p| (p.lat, p.lon)).collect();
+    local.extend(found.into_iter().filter(|place| {
+        !known
+            .iter()
+            .any(|(lat, lon)| (place.lat - lat).abs() < 0.1 && (place.lon - lon).abs() < 0.1)
+    }));
+    local
+}
+
+impl App {
+    fn render_input(&self, area: Rect, buf: &mut Buffer) {
+        let (text, title) = match &self.history_search {
+            Some(search) => (search.query.visible(), "History search"),
+            None => (self.input.visible(), "City"),
         };
-        Ok(())
+        Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(ratatui::widgets::Borders::ALL)
+                    .title(title)
+                    .title_alignment(ratatui::layout::Alignment::Center),
+            )
+            .render(area, buf);
+
+        // the match goes next to the box, the box itself shows what is being searched for
+        if let Some(search) = &self.history_search {
+            let matched = match search.matched {
+                Some(index) => format!("> {}", self.history.entries()[index]),
+                None if search.query.is_empty() => "Type to search the history".to_string(),
+                None => "No match".to_string(),
+            };
+            let match_area = Rect {
+                x: area.right() + 1,
+                y: area.y + 1,
+                width: buf.area.right().saturating_sub(area.right() + 2),
+                height: 1,
+            };
+            Line::from(matched).italic().render(match_area, buf);
+        }
     }
 
-    fn exit(&mut self) {
-        self.exit = true;
+    // Drops up from the input box since it sits at the bottom of the screen
+    fn render_suggestions(&self, input_area: Rect, buf: &mut Buffer) {
+        if self.suggestions.is_empty() || self.history_search.is_some() {
+            return;
+        }
+        let longest = self
+            .suggestions
+            .iter()
+            .map(|s| s.width())
+            .max()
+            .unwrap_or(0) as u16;
+        let height = (self.suggestions.len() as u16 + 2).min(input_area.y);
+        let area = Rect {
+            x: input_area.x,
+            y: input_area.y - height,
+            width: (longest + 4)
+                .max(input_area.width)
+                .min(buf.area.right().saturating_sub(input_area.x)),
+            height,
+        };
+
+        let block = Block::bordered()
+            .title_bottom(Line::from(" Tab ").italic().centered())
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(self.theme.accent));
+        let mut state = ListState::default().with_selected(Some(self.selected_suggestion));
+        Clear.render(area, buf);
+        StatefulWidget::render(
+            List::new(self.suggestions.iter().map(String::as_str))
+                .block(block)
+                .highlight_style(Style::default().reversed()),
+            area,
+            buf,
+            &mut state,
+        );
     }
 
-    fn handle_weather_fetch(&mut self) {
-        let city = self.city.clone();
-        let weather_details_arc = Arc::clone(&self.weather_details);
-        let isfetching_arc = Arc::clone(&self.isfetching);
-        tokio::spawn(async move {
-            let response = fetch_weather(&city).await;
-            if response.status().is_success() {
-                let weather_text = response.text().await.expect("Failed to read response text");
-                let details: WeatherDetails =
-                    serde_json::from_str(&weather_text).expect("Failed to parse JSON");
-                let mut weather_details = weather_details_arc
-                    .lock()
-                    .expect("weather_details poisoned");
-                isfetching_arc.store(false, Ordering::SeqCst);
-                *weather_details = Some(details);
-            } else {
-                let mut weather_details = weather_details_arc
-                    .lock()
-                    .expect("weather_details poisoned");
-                isfetching_arc.store(false, Ordering::SeqCst);
-                *weather_details = None;
+    // Candidates of an ambiguous search, drawn over the middle of the content area
+    fn render_picker(&self, area: Rect, buf: &mut Buffer) {
+        let Some(places) = self.candidates.as_ref() else {
+            return;
+        };
+        let [_, row, _] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(places.len() as u16 + 2),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [_, picker_area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Percentage(60),
+            Constraint::Fill(1),
+        ])
+        .areas(row);
+
+        let block = Block::bordered()
+            .title(Line::from(" Which one? ").bold().centered())
+            .title_bottom(Line::from(" Enter pick  Esc cancel ").italic().centered())
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(self.theme.accent));
+        let mut state = ListState::default().with_selected(Some(self.selected_candidate));
+        Clear.render(picker_area, buf);
+        StatefulWidget::render(
+            List::new(places.iter().map(Place::label))
+                .block(block)
+                .highlight_symbol("> ")
+                .highlight_style(Style::default().reversed()),
+            picker_area,
+            buf,
+            &mut state,
+        );
+    }
+
+    // " F1 help  Esc navigate " with the first key of each action in the current mode,
+    // actions without one are left out
+    fn key_hints(&self, hints: &[(Action, &str)]) -> String {
+        let mut text = String::from(" ");
+        for (action, label) in hints {
+            if let Some(key) = self.keymap.key(self.mode, *action) {
+                text.push_str(&format!("{} {}  ", key, label));
             }
-        });
+        }
+        text.pop();
+        text
+    }
+
+    // Sorting works in navigation mode only, where the comparison's keys go first
+    fn sort_hint(&self) -> Option<String> {
+        self.keymap.comparison_key(Action::SortColumn).map(|key| {
+            format!(
+                " {} sort by column in navigation mode, again to reverse ",
+                key.label(SortColumn::ALL.len())
+            )
+        })
+    }
+
+    // Lists the keymap of the current mode, it is generated so it also shows what the
+    // config file rebound
+    fn render_help(&self, area: Rect, buf: &mut Buffer) {
+        if !self.show_help {
+            return;
+        }
+        let title = match self.mode {
+            Mode::Typing => " Keys while typing ",
+            Mode::Navigation => " Keys ",
+        };
+        let bindings: Vec<(Action, Vec<KeyBinding>)> = match self.mode {
+            Mode::Navigation if self.comparing => self.keymap.comparison_bindings(),
+            mode => self
+                .keymap
+                .bindings(mode)
+                .map(|(action, keys)| (action, keys.to_vec()))
+                .collect(),
+        };
+        let rows: Vec<(String, &str)> = bindings
+            .iter()
+            .map(|(action, keys)| {
+                let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+                (keys.join(", "), action.description())
+            })
+            .collect();
+        let key_width = rows.iter().map(|(keys, _)| keys.width()).max().unwrap_or(0);
+        let lines: Vec<Line> = rows
+            .iter()
+            .map(|(keys, what)| {
+                Line::from(vec![
+                    Span::from(format!(" {:width$}  ", keys, width = key_width)).bold(),
+                    Span::from(*what),
+                ])
+            })
+            .collect();
+
+        let [_, row, _] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(lines.len() as u16 + 2),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [_, help_area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(key_width as u16 + 26),
+            Constraint::Fill(1),
+        ])
+        .areas(row);
+        let block = Block::bordered()
+            .title(Line::from(title).bold().centered())
+            .title_bottom(Line::from(" any key closes ").italic().centered())
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(self.theme.accent));
+        Clear.render(help_area, buf);
+        Paragraph::new(lines).block(block).render(help_area, buf);
+    }
+
+    fn render_favorites(&self, area: Rect, buf: &mut Buffer) {
+        let hint = self.key_hints(&[
+            (Action::SaveFavorite, "save"),
+            (Action::RemoveFavorite, "del"),
+        ]);
+        let block = Block::bordered()
+            .title(Line::from(" Favorites ").bold().centered())
+            .title_bottom(Line::from(hint).italic().centered())
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        if let Some(error) = &self.favorites_error {
+            Paragraph::new(error.as_str())
+                .wrap(Wrap { trim: true })
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+        if self.favorites.is_empty() {
+            Paragraph::new("No favorites yet.")
+                .centered()
+                .block(block)
+                .render(area, buf);
+            return;
+        }
+
+        let items = self
+            .favorites
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, favorite)| format!("{} {}", i + 1, favorite.label()));
+        let mut state = ListState::default().with_selected(self.selected_favorite);
+        StatefulWidget::render(
+            List::new(items)
+                .block(block)
+                .highlight_symbol("> ")
+                .highlight_style(Style::default().reversed()),
+            area,
+            buf,
+            &mut state,
+        );
     }
 }
 
@@ -196,47 +919,44 @@ impl Widget for &App {
     // The render method runs every time the UI needs to be redrawn so no any mutations should be done here
     fn render(self, area: Rect, buf: &mut Buffer) {
         let title = Line::from(" TermoCast ").bold().underlined();
-        let instruction =
-            Line::from(" Type a city name and press Enter. Press 'Esc' or 'Ctrl+C' to quit. ")
-                .italic();
+        let hints = match self.mode {
+            Mode::Typing => self.key_hints(&[
+                (Action::Search, "search"),
+                (Action::Help, "help"),
+                (Action::Navigate, "navigate"),
+                (Action::Quit, "quit"),
+            ]),
+            Mode::Navigation => self.key_hints(&[
+                (Action::Help, "help"),
+                (Action::Type, "type"),
+                (Action::Quit, "quit"),
+            ]),
+        };
+        let instruction = Line::from(hints).italic();
         let block = Block::bordered()
             .title(title.centered())
             .title_bottom(instruction.centered())
-            .border_set(border::ROUNDED);
-
-        let weather_info = if let Some(details) = &self
-            .weather_details
-            .lock()
-            .expect("weather_details poisoned")
-            .as_ref()
-        {
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let weather_info = if let Some(details) = &self.weather_details {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
             format!(
-                "{}\n\nCity: {}\nTemperature: {:.2}°C\nMin Temp: {:.2}°C\nMax Temp: {:.2}°C\nHumidity: {}%\nPressure: {} hPa\nWind Speed: {:.2} m/s\nWind Direction: {}°\nCloudiness: {}%\nDescription: {}\n",
-                self.art.get_art(
-                    details
-                        .weather
-                        .first()
-                        .map_or("N/A", |w| w.description.as_str()),
-                    is_day(details)
-                ),
-                details.name,
-                details.main.temp - 273.15,
-                details.main.temp_min - 273.15,
-                details.main.temp_max - 273.15,
-                details.main.humidity,
-                details.main.pressure,
-                details.wind.speed,
-                details.wind.deg,
-                details.clouds.all,
-                details
-                    .weather
-                    .first()
-                    .map_or("N/A", |w| w.description.as_str())
+                "{}\nUpdated {}{}",
+                format_reading(details, Some(&self.art), &self.units),
+                format_age(now.saturating_sub(details.dt)),
+                if details.stale {
+                    " (cached, offline)"
+                } else {
+                    ""
+                }
             )
-        } else if self.isfetching.load(Ordering::SeqCst) {
+        } else if self.isfetching {
             "\nFetching weather data...".to_string()
-        } else if self.fetched_once {
-            "\nCity not found or error fetching data.".to_string()
+        } else if let Some(error) = &self.error {
+            format!("\n{}", error)
         } else {
             "\nPlease enter a city name to get the weather information.".to_string()
         };
@@ -244,42 +964,520 @@ impl Widget for &App {
         let input_area = Rect {
             x: area.x + 2,
             y: area.y + area.height - 3,
-            width: 20,
+            width: INPUT_WIDTH,
             height: 3,
         };
 
         let _ = execute!(io::stdout(), cursor::Show, cursor::EnableBlinking);
 
-        Paragraph::new(self.city.as_str())
-            .block(
-                Block::default()
-                    .borders(ratatui::widgets::Borders::ALL)
-                    .title("City")
-                    .title_alignment(ratatui::layout::Alignment::Center),
-            )
-            .render(input_area, buf);
+        let inner = block.inner(area);
+        block.render(area, buf);
 
-        Paragraph::new(weather_info.as_str())
-            .block(block)
-            .centered()
-            .render(area, buf);
+        // the bottom rows are kept free for the city input box
+        let [body_area, _] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(inner);
+        let [sidebar_area, content_area] =
+            Layout::horizontal([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(0)])
+                .areas(body_area);
+        self.render_favorites(sidebar_area, buf);
+
+        if self.comparing {
+            CompareTable {
+                rows: &self.compare_rows,
+                sort: self.compare_sort,
+                hint: self.sort_hint(),
+                units: &self.units,
+                theme: &self.theme,
+            }
+            .render(content_area, buf);
+            self.render_input(input_area, buf);
+            self.render_suggestions(input_area, buf);
+            self.render_picker(content_area, buf);
+            self.render_help(area, buf);
+            return;
+        }
+
+        let forecast = &self.forecast;
+        let forecast_height = if forecast.is_some() {
+            FORECAST_HEIGHT
+        } else {
+            0
+        };
+        let [current_area, forecast_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(forecast_height)])
+                .areas(content_area);
+
+        if let Some(forecast) = forecast.as_ref() {
+            // the reading keeps the left half, the hourly chart gets its own area on the right
+            let [reading_area, chart_area] =
+                Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .areas(current_area);
+            Paragraph::new(weather_info.as_str())
+                .centered()
+                .render(reading_area, buf);
+            HourlyChart {
+                forecast,
+                units: &self.units,
+                theme: &self.theme,
+            }
+            .render(chart_area, buf);
+            ForecastPanel {
+                forecast,
+                art: &self.art,
+                units: &self.units,
+                theme: &self.theme,
+            }
+            .render(forecast_area, buf);
+        } else {
+            Paragraph::new(weather_info.as_str())
+                .centered()
+                .render(current_area, buf);
+        }
+
+        self.render_input(input_area, buf);
+        self.render_suggestions(input_area, buf);
+        self.render_picker(content_area, buf);
+        self.render_help(area, buf);
     }
 }
 
-pub fn tui() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn tui(
+    provider: Arc<dyn WeatherProvider>,
+    settings: Settings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let favorites = Favorites::load()?;
+    let history = History::load()?;
+    let (sender, receiver) = unbounded_channel();
     let mut terminal = ratatui::init();
-    let app_result = App::default().run(&mut terminal);
+    let app_result = App::new(provider, settings, favorites, history, sender)
+        .run(&mut terminal, receiver)
+        .await;
     ratatui::restore();
     app_result
 }
 
-pub fn is_day(details: &WeatherDetails) -> bool {
-    // adding the offset to convert to local time though it's not strictly necessary for this comparison
-    let current_time = details.dt as i64 + details.timezone;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::tests::{Stub, TempDir};
+    use crate::cache::{Cache, CachedProvider};
+    use crate::provider::ProviderKind;
+    use crossterm::event::KeyModifiers;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tokio::sync::oneshot;
+
+    const WEATHER: &str = include_str!("../tests/fixtures/openweathermap/weather.json");
+
+    // Answers `current` only once the test opens the gate for that location, so tests
+    // decide in which order fetches finish
+    #[derive(Debug, Default)]
+    struct GatedProvider {
+        gates: Mutex<HashMap<String, oneshot::Receiver<()>>>,
+    }
+
+    impl GatedProvider {
+        fn gate(&self, location: &Location) -> oneshot::Sender<()> {
+            let (open, gate) = oneshot::channel();
+            self.gates
+                .lock()
+                .unwrap()
+                .insert(location.to_string(), gate);
+            open
+        }
+
+        // Wait until a fetch for `location` is in flight, waiting on its gate
+        async fn started(&self, location: &Location) {
+            while self
+                .gates
+                .lock()
+                .unwrap()
+                .contains_key(&location.to_string())
+            {
+                tokio::task::yield_now().await;
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl WeatherProvider for GatedProvider {
+        async fn current(&self, location: &Location) -> Result<WeatherDetails, TermocastError> {
+            let gate = self.gates.lock().unwrap().remove(&location.to_string());
+            if let Some(gate) = gate {
+                let _ = gate.await;
+            }
+            let mut details: WeatherDetails = serde_json::from_str(WEATHER)?;
+            details.name = location.to_string();
+            Ok(details)
+        }
+
+        async fn forecast(&self, location: &Location) -> Result<Forecast, TermocastError> {
+            Err(TermocastError::CityNotFound(location.to_string()))
+        }
+
+        async fn geocode(&self, query: &str) -> Result<Vec<Place>, TermocastError> {
+            Err(TermocastError::CityNotFound(query.to_string()))
+        }
+    }
+
+    // Geocodes every name to the same places and counts how often it was asked
+    #[derive(Debug, Default)]
+    struct Geocoder {
+        places: Vec<Place>,
+        asked: Mutex<usize>,
+    }
+
+    #[async_trait::async_trait]
+    impl WeatherProvider for Geocoder {
+        async fn current(&self, location: &Location) -> Result<WeatherDetails, TermocastError> {
+            Err(TermocastError::CityNotFound(location.to_string()))
+        }
+
+        async fn forecast(&self, location: &Location) -> Result<Forecast, TermocastError> {
+            Err(TermocastError::CityNotFound(location.to_string()))
+        }
+
+        async fn geocode(&self, query: &str) -> Result<Vec<Place>, TermocastError> {
+            *self.asked.lock().unwrap() += 1;
+            if self.places.is_empty() {
+                return Err(TermocastError::CityNotFound(query.to_string()));
+            }
+            Ok(self.places.clone())
+        }
+    }
+
+    fn london(country: &str, lat: f64, lon: f64) -> Place {
+        Place {
+            name: "London".to_string(),
+            state: None,
+            country: Some(country.to_string()),
+            lat,
+            lon,
+        }
+    }
+
+    fn londons() -> Geocoder {
+        Geocoder {
+            places: vec![
+                london("GB", 51.5073, -0.1276),
+                london("CA", 42.9834, -81.233),
+            ],
+            ..Default::default()
+        }
+    }
+
+    async fn fetched(provider: &Geocoder, query: &str, offline: bool) -> FetchOutcome {
+        fetch_weather(provider, Location::Name(query.to_string()), offline, false).await
+    }
+
+    #[tokio::test]
+    async fn bundled_cities_join_the_geocoded_ones() {
+        let provider = londons();
+        let FetchOutcome::Ambiguous(places) = fetched(&provider, "London", false).await else {
+            panic!("expected the picker");
+        };
+        // the geocoder's copy of the bundled London is dropped
+        assert_eq!(places.len(), 2);
+        assert_eq!(places[0].state.as_deref(), Some("England"));
+        assert_eq!(places[1].country.as_deref(), Some("CA"));
+        assert_eq!(*provider.asked.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_country_code_skips_the_geocoder() {
+        let provider = londons();
+        let FetchOutcome::Fetched { location, .. } = fetched(&provider, "London,GB", false).await
+        else {
+            panic!("expected a fetch");
+        };
+        assert_eq!(location.to_string(), "London");
+        assert!(matches!(location, Location::Coordinates { lat, .. } if lat == 51.5085));
+        assert_eq!(*provider.asked.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn offline_the_bundled_cities_decide() {
+        let provider = londons();
+        let outcome = fetched(&provider, "London", true).await;
+        assert!(matches!(outcome, FetchOutcome::Fetched { .. }));
+        assert_eq!(*provider.asked.lock().unwrap(), 0);
+
+        let FetchOutcome::Ambiguous(places) = fetched(&provider, "Springfield", true).await else {
+            panic!("expected the picker");
+        };
+        assert_eq!(places.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn bundled_cities_stand_in_for_a_failed_geocoder() {
+        let provider = Geocoder::default();
+        let outcome = fetched(&provider, "London", false).await;
+        assert!(matches!(outcome, FetchOutcome::Fetched { .. }));
+        assert_eq!(*provider.asked.lock().unwrap(), 1);
+
+        let outcome = fetched(&provider, "Atlantis", false).await;
+        assert!(matches!(
+            outcome,
+            FetchOutcome::Failed(TermocastError::CityNotFound(_))
+        ));
+    }
+
+    fn place(name: &str) -> Location {
+        Location::Coordinates {
+            lat: 1.0,
+            lon: 2.0,
+            name: Some(name.to_string()),
+        }
+    }
+
+    fn app(provider: Arc<dyn WeatherProvider>) -> (App, UnboundedReceiver<AppEvent>) {
+        let settings = Settings {
+            provider: ProviderKind::OpenMeteo,
+            api_key: None,
+            language: None,
+            base_url: None,
+            default_city: None,
+            refresh_interval: Duration::from_secs(600),
+            cache_ttl: Duration::from_secs(300),
+            offline: false,
+            units: Units::default(),
+            theme: Theme::default(),
+            keybindings: Keymap::default(),
+        };
+        let (sender, receiver) = unbounded_channel();
+        let app = App::new(
+            provider,
+            settings,
+            Favorites::default(),
+            History::default(),
+            sender,
+        );
+        (app, receiver)
+    }
+
+    fn shown(app: &App) -> Option<&str> {
+        app.weather_details.as_ref().map(|d| d.name.as_str())
+    }
+
+    #[tokio::test]
+    async fn newer_search_aborts_the_older_one() {
+        let provider = Arc::new(GatedProvider::default());
+        let mut first = provider.gate(&place("First"));
+        let second = provider.gate(&place("Second"));
+        let (mut app, mut events) = app(provider.clone());
+
+        app.handle_weather_fetch(place("First"));
+        provider.started(&place("First")).await;
+        app.handle_weather_fetch(place("Second"));
+
+        // the aborted task is dropped along with its gate
+        tokio::time::timeout(Duration::from_secs(1), first.closed())
+            .await
+            .expect("the first fetch was not aborted");
 
-    let sunrise_time = details.sys.sunrise as i64 + details.timezone;
+        second.send(()).unwrap();
+        let event = events.recv().await.unwrap();
+        app.handle_app_event(event);
+        assert_eq!(shown(&app), Some("Second"));
+        assert!(!app.isfetching);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn late_result_of_a_superseded_search_is_dropped() {
+        let provider = Arc::new(GatedProvider::default());
+        let first = provider.gate(&place("First"));
+        let second = provider.gate(&place("Second"));
+        let (mut app, mut events) = app(provider);
+
+        // the first fetch finishes, but its result is only handled after the second
+        // search started
+        app.handle_weather_fetch(place("First"));
+        first.send(()).unwrap();
+        let late = events.recv().await.unwrap();
+        app.handle_weather_fetch(place("Second"));
+
+        app.handle_app_event(late);
+        assert_eq!(shown(&app), None);
+        assert!(app.isfetching);
+
+        second.send(()).unwrap();
+        let event = events.recv().await.unwrap();
+        app.handle_app_event(event);
+        assert_eq!(shown(&app), Some("Second"));
+        assert!(!app.isfetching);
+    }
+
+    #[tokio::test]
+    async fn results_in_order_show_the_latest() {
+        let provider = Arc::new(GatedProvider::default());
+        let first = provider.gate(&place("First"));
+        let second = provider.gate(&place("Second"));
+        let (mut app, mut events) = app(provider);
+
+        app.handle_weather_fetch(place("First"));
+        first.send(()).unwrap();
+        let event = events.recv().await.unwrap();
+        app.handle_app_event(event);
+        assert_eq!(shown(&app), Some("First"));
+        assert!(!app.isfetching);
+
+        app.handle_weather_fetch(place("Second"));
+        assert!(app.isfetching);
+        second.send(()).unwrap();
+        let event = events.recv().await.unwrap();
+        app.handle_app_event(event);
+        assert_eq!(shown(&app), Some("Second"));
+        assert!(!app.isfetching);
+        assert_eq!(app.generation, 2);
+    }
+
+    fn press(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+        app.handle_event(Event::Key(KeyEvent::new(code, modifiers)));
+    }
+
+    fn with_favorites(app: &mut App) {
+        for (i, name) in ["Oslo", "Lima"].into_iter().enumerate() {
+            app.favorites.add(Favorite {
+                name: name.to_string(),
+                country: None,
+                lat: i as f64 * 10.0,
+                lon: 0.0,
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn digits_are_typed_while_comparing() {
+        let (mut app, _events) = app(Arc::new(GatedProvider::default()));
+        with_favorites(&mut app);
+        app.comparing = true;
+        press(&mut app, KeyCode::Char('2'), KeyModifiers::NONE);
+        assert_eq!(app.input.value(), "2");
+        assert_eq!(app.compare_sort, Sort::default());
+        assert_eq!(app.selected_favorite, None);
+    }
 
-    let sunset_time = details.sys.sunset as i64 + details.timezone;
+    #[tokio::test]
+    async fn digits_sort_the_comparison_in_navigation_mode() {
+        let (mut app, _events) = app(Arc::new(GatedProvider::default()));
+        with_favorites(&mut app);
+        app.comparing = true;
+        app.mode = Mode::Navigation;
+        press(&mut app, KeyCode::Char('2'), KeyModifiers::NONE);
+        assert_eq!(app.compare_sort.column, SortColumn::Temperature);
+        press(&mut app, KeyCode::Char('2'), KeyModifiers::NONE);
+        assert!(app.compare_sort.descending);
+        // past the last column digits still belong to the comparison
+        press(&mut app, KeyCode::Char('7'), KeyModifiers::NONE);
+        assert_eq!(app.compare_sort.column, SortColumn::Temperature);
+        assert_eq!(app.selected_favorite, None);
+        press(&mut app, KeyCode::Char('2'), KeyModifiers::ALT);
+        assert_eq!(app.selected_favorite, Some(1));
+    }
+
+    #[tokio::test]
+    async fn sort_keys_come_from_the_keymap() {
+        let (mut app, _events) = app(Arc::new(GatedProvider::default()));
+        with_favorites(&mut app);
+        app.keymap = toml::from_str("comparison = { sort_column = \"tab\" }").unwrap();
+        app.comparing = true;
+        app.mode = Mode::Navigation;
+        press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        assert_eq!(app.compare_sort.column, SortColumn::Temperature);
+        assert_eq!(
+            app.sort_hint().unwrap(),
+            " Tab sort by column in navigation mode, again to reverse "
+        );
+        // the digits are free for the favorites again
+        press(&mut app, KeyCode::Char('2'), KeyModifiers::NONE);
+        assert_eq!(app.compare_sort.column, SortColumn::Temperature);
+        assert_eq!(app.selected_favorite, Some(1));
+    }
 
-    current_time >= sunrise_time && current_time < sunset_time
+    #[tokio::test]
+    async fn digits_jump_to_favorites() {
+        let (mut app, _events) = app(Arc::new(GatedProvider::default()));
+        with_favorites(&mut app);
+        press(&mut app, KeyCode::Char('2'), KeyModifiers::ALT);
+        assert_eq!(app.selected_favorite, Some(1));
+        assert!(app.input.is_empty());
+
+        app.mode = Mode::Navigation;
+        press(&mut app, KeyCode::Char('1'), KeyModifiers::NONE);
+        assert_eq!(app.selected_favorite, Some(0));
+        // there is no ninth favorite
+        press(&mut app, KeyCode::Char('9'), KeyModifiers::NONE);
+        assert_eq!(app.selected_favorite, Some(0));
+    }
+
+    #[tokio::test]
+    async fn input_keys_follow_the_keymap() {
+        let (mut app, _events) = app(Arc::new(GatedProvider::default()));
+        app.keymap =
+            toml::from_str("typing = { search = \"ctrl+j\", history_older = \"ctrl+p\" }").unwrap();
+        for c in "Oslo".chars() {
+            press(&mut app, KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(!app.isfetching);
+        press(&mut app, KeyCode::Char('j'), KeyModifiers::CONTROL);
+        assert!(app.isfetching);
+        assert!(app.input.is_empty());
+
+        press(&mut app, KeyCode::Up, KeyModifiers::NONE);
+        assert!(app.input.is_empty());
+        press(&mut app, KeyCode::Char('p'), KeyModifiers::CONTROL);
+        assert_eq!(app.input.value(), "Oslo");
+    }
+
+    #[tokio::test]
+    async fn the_picker_uses_the_typing_keys() {
+        let (mut app, _events) = app(Arc::new(GatedProvider::default()));
+        app.keymap = toml::from_str("typing = { history_newer = \"ctrl+n\" }").unwrap();
+        app.mode = Mode::Navigation;
+        app.candidates = Some(vec![
+            Place {
+                name: "Springfield".to_string(),
+                state: Some("Illinois".to_string()),
+                country: None,
+                lat: 1.0,
+                lon: 2.0,
+            };
+            2
+        ]);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!(app.selected_candidate, 0);
+        press(&mut app, KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(app.selected_candidate, 1);
+        // Esc closes the picker even though it quits in navigation mode
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(app.candidates.is_none());
+        assert!(!app.exit);
+    }
+
+    #[tokio::test]
+    async fn refresh_asks_the_service_despite_a_fresh_cache() {
+        let dir = TempDir::new("ui-refresh");
+        let stub = Arc::new(Stub::default());
+        let provider = Arc::new(CachedProvider {
+            inner: stub.clone(),
+            cache: Cache::new(Some(dir.0.clone()), Duration::from_secs(300)),
+            offline: false,
+        });
+        let (mut app, mut events) = app(provider);
+
+        app.handle_weather_fetch(place("London"));
+        app.handle_app_event(events.recv().await.unwrap());
+        // current and forecast
+        assert_eq!(stub.calls(), 2);
+
+        for round in 1..=2 {
+            app.refresh();
+            app.handle_app_event(events.recv().await.unwrap());
+            assert_eq!(stub.calls(), 2 + round * 2);
+        }
+        assert!(!app.isfetching);
+        assert!(!app.weather_details.as_ref().unwrap().stale);
+    }
 }
@@ -0,0 +1,284 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+// Readings are kept the way OpenWeatherMap reports them by default (kelvin, m/s, hPa)
+// and only converted when they are displayed, so switching units never needs a refetch.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl TemperatureUnit {
+    pub fn convert(self, kelvin: f32) -> f32 {
+        match self {
+            Self::Celsius => kelvin - 273.15,
+            Self::Fahrenheit => (kelvin - 273.15) * 9.0 / 5.0 + 32.0,
+            Self::Kelvin => kelvin,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Celsius => "°C",
+            Self::Fahrenheit => "°F",
+            Self::Kelvin => "K",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindUnit {
+    #[default]
+    #[value(name = "ms")]
+    #[serde(rename = "ms")]
+    MetersPerSecond,
+    #[value(name = "kmh")]
+    #[serde(rename = "kmh")]
+    KilometersPerHour,
+    Mph,
+    Knots,
+    Beaufort,
+}
+
+// Lowest speed in m/s of Beaufort forces 1 to 12, below the first one is force 0. The
+// scale lists force 11 as 28.5 to 32.6 m/s, so 32.6 itself is still force 11.
+const BEAUFORT_LIMITS: [f32; 12] = [
+    0.5, 1.6, 3.4, 5.5, 8.0, 10.8, 13.9, 17.2, 20.8, 24.5, 28.5, 32.7,
+];
+
+impl WindUnit {
+    pub fn convert(self, speed: f32) -> f32 {
+        match self {
+            Self::MetersPerSecond => speed,
+            Self::KilometersPerHour => speed * 3.6,
+            Self::Mph => speed * 2.236_936,
+            Self::Knots => speed * 1.943_844,
+            Self::Beaufort => BEAUFORT_LIMITS
+                .iter()
+                .take_while(|&&limit| speed >= limit)
+                .count() as f32,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::MetersPerSecond => "m/s",
+            Self::KilometersPerHour => "km/h",
+            Self::Mph => "mph",
+            Self::Knots => "kn",
+            Self::Beaufort => "Bft",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureUnit {
+    #[default]
+    Hpa,
+    Inhg,
+    Mmhg,
+}
+
+impl PressureUnit {
+    pub fn convert(self, pressure: f32) -> f32 {
+        match self {
+            Self::Hpa => pressure,
+            Self::Inhg => pressure * 0.029_529_983,
+            Self::Mmhg => pressure * 0.750_061_7,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Hpa => "hPa",
+            Self::Inhg => "inHg",
+            Self::Mmhg => "mmHg",
+        }
+    }
+}
+
+// Presets for the three dimensions, cycled through at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    Metric,
+    Imperial,
+    Standard,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub wind: WindUnit,
+    pub pressure: PressureUnit,
+}
+
+impl From<UnitSystem> for Units {
+    fn from(system: UnitSystem) -> Self {
+        match system {
+            UnitSystem::Metric => Self {
+                temperature: TemperatureUnit::Celsius,
+                wind: WindUnit::MetersPerSecond,
+                pressure: PressureUnit::Hpa,
+            },
+            UnitSystem::Imperial => Self {
+                temperature: TemperatureUnit::Fahrenheit,
+                wind: WindUnit::Mph,
+                pressure: PressureUnit::Inhg,
+            },
+            UnitSystem::Standard => Self {
+                temperature: TemperatureUnit::Kelvin,
+                wind: WindUnit::MetersPerSecond,
+                pressure: PressureUnit::Hpa,
+            },
+        }
+    }
+}
+
+impl Units {
+    // Move to the next preset, custom combinations restart the cycle at metric
+    pub fn next_system(self) -> Self {
+        let next = match self {
+            s if s == UnitSystem::Metric.into() => UnitSystem::Imperial,
+            s if s == UnitSystem::Imperial.into() => UnitSystem::Standard,
+            _ => UnitSystem::Metric,
+        };
+        next.into()
+    }
+
+    pub fn temperature(&self, kelvin: f32) -> String {
+        format!(
+            "{:.2}{}",
+            self.temperature.convert(kelvin),
+            self.temperature.symbol()
+        )
+    }
+
+    // Whole degrees for places with little room, like the forecast columns
+    pub fn temperature_rounded(&self, kelvin: f32) -> String {
+        format!(
+            "{:.0}{}",
+            self.temperature.convert(kelvin),
+            self.temperature.symbol()
+        )
+    }
+
+    pub fn wind(&self, meters_per_second: f32) -> String {
+        let speed = self.wind.convert(meters_per_second);
+        match self.wind {
+            WindUnit::Beaufort => format!("{} {:.0}", self.wind.symbol(), speed),
+            _ => format!("{:.2} {}", speed, self.wind.symbol()),
+        }
+    }
+
+    pub fn pressure(&self, hectopascal: f32) -> String {
+        let pressure = self.pressure.convert(hectopascal);
+        match self.pressure {
+            PressureUnit::Inhg => format!("{:.2} {}", pressure, self.pressure.symbol()),
+            _ => format!("{:.0} {}", pressure, self.pressure.symbol()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn kelvin_to_celsius_and_fahrenheit() {
+        assert_close(TemperatureUnit::Celsius.convert(273.15), 0.0);
+        assert_close(TemperatureUnit::Celsius.convert(300.0), 26.85);
+        assert_close(TemperatureUnit::Fahrenheit.convert(273.15), 32.0);
+        assert_close(TemperatureUnit::Fahrenheit.convert(373.15), 212.0);
+        assert_close(TemperatureUnit::Fahrenheit.convert(233.15), -40.0);
+        assert_close(TemperatureUnit::Kelvin.convert(288.4), 288.4);
+    }
+
+    #[test]
+    fn wind_speed_factors() {
+        assert_close(WindUnit::MetersPerSecond.convert(10.0), 10.0);
+        assert_close(WindUnit::KilometersPerHour.convert(10.0), 36.0);
+        assert_close(WindUnit::Mph.convert(10.0), 22.36936);
+        assert_close(WindUnit::Knots.convert(10.0), 19.43844);
+    }
+
+    #[test]
+    fn beaufort_boundaries() {
+        let force = |speed| WindUnit::Beaufort.convert(speed);
+        assert_eq!(force(0.0), 0.0);
+        assert_eq!(force(0.49), 0.0);
+        assert_eq!(force(0.5), 1.0);
+        assert_eq!(force(1.5), 1.0);
+        assert_eq!(force(5.5), 4.0);
+        assert_eq!(force(28.5), 11.0);
+        assert_eq!(force(32.6), 11.0);
+        assert_eq!(force(32.7), 12.0);
+        assert_eq!(force(60.0), 12.0);
+    }
+
+    #[test]
+    fn pressure_factors() {
+        assert_close(PressureUnit::Hpa.convert(1013.25), 1013.25);
+        assert_close(PressureUnit::Inhg.convert(1013.25), 29.921);
+        assert_close(PressureUnit::Mmhg.convert(1013.25), 760.0);
+    }
+
+    #[test]
+    fn formatted_readings() {
+        let metric = Units::from(UnitSystem::Metric);
+        let imperial = Units::from(UnitSystem::Imperial);
+        assert_eq!(metric.temperature(293.15), "20.00°C");
+        assert_eq!(imperial.temperature_rounded(293.15), "68°F");
+        assert_eq!(imperial.wind(10.0), "22.37 mph");
+        assert_eq!(imperial.pressure(1013.25), "29.92 inHg");
+        let beaufort = Units {
+            wind: WindUnit::Beaufort,
+            ..metric
+        };
+        assert_eq!(beaufort.wind(4.0), "Bft 3");
+    }
+
+    #[test]
+    fn next_system_cycles_the_presets() {
+        let metric = Units::from(UnitSystem::Metric);
+        let imperial = Units::from(UnitSystem::Imperial);
+        let standard = Units::from(UnitSystem::Standard);
+        assert_eq!(Units::default(), metric);
+        assert_eq!(metric.next_system(), imperial);
+        assert_eq!(imperial.next_system(), standard);
+        assert_eq!(standard.next_system(), metric);
+    }
+
+    #[test]
+    fn custom_combinations_restart_at_metric() {
+        let custom = Units {
+            temperature: TemperatureUnit::Celsius,
+            wind: WindUnit::Knots,
+            pressure: PressureUnit::Mmhg,
+        };
+        assert_eq!(custom.next_system(), Units::from(UnitSystem::Metric));
+
+        // metric with one dimension overridden is not metric anymore
+        let metric_in_kmh = Units {
+            wind: WindUnit::KilometersPerHour,
+            ..Units::from(UnitSystem::Metric)
+        };
+        assert_eq!(metric_in_kmh.next_system(), Units::from(UnitSystem::Metric));
+    }
+}